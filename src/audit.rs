@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use mongodb::bson::DateTime;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use tracing::debug;
+
+use crate::{Cli, history};
+use mongo_copy::{CopyStats, PartialCopy, mask_uri};
+
+/// One completed copy operation, written as a JSON line
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    pub operation: String,
+    pub user: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub source: String,
+    pub destination: String,
+    pub source_namespace: String,
+    pub destination_namespace: String,
+    pub documents: u64,
+    pub bytes: u64,
+    /// Oversized documents left out of the copy
+    #[serde(skip_serializing_if = "is_zero")]
+    pub skipped: u64,
+    pub outcome: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    /// Begin an entry, capturing the start time and masking both URIs
+    pub fn start(
+        operation: &str,
+        source_uri: &str,
+        dest_uri: &str,
+        source_namespace: &str,
+        destination_namespace: &str,
+    ) -> Self {
+        Self {
+            operation: operation.to_string(),
+            user: std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .ok(),
+            started_at: now(),
+            finished_at: None,
+            source: mask_uri(source_uri),
+            destination: mask_uri(dest_uri),
+            source_namespace: source_namespace.to_string(),
+            destination_namespace: destination_namespace.to_string(),
+            documents: 0,
+            bytes: 0,
+            skipped: 0,
+            outcome: "pending",
+            error: None,
+        }
+    }
+
+    pub fn finish(mut self, result: &Result<CopyStats>) -> Self {
+        self.finished_at = Some(now());
+        match result {
+            Ok(stats) => {
+                self.documents = stats.documents;
+                self.bytes = stats.bytes;
+                self.skipped = stats.skipped;
+                self.outcome = "success";
+            }
+            Err(e) => {
+                if let Some(PartialCopy(stats)) = e.downcast_ref() {
+                    self.documents = stats.documents;
+                    self.bytes = stats.bytes;
+                    self.skipped = stats.skipped;
+                }
+                self.outcome = "failed";
+                self.error = Some(format!("{:#}", e));
+            }
+        }
+        self
+    }
+}
+
+/// Add `entry` to the operation history, unless disabled, and append it to
+/// the audit log if one was requested
+pub fn record(cli: &Cli, entry: &AuditEntry) -> Result<()> {
+    if !cli.no_history {
+        history::append(entry);
+    }
+    let Some(path) = cli.audit_log.as_deref() else {
+        return Ok(());
+    };
+    debug!("Writing audit entry to: {:?}", path);
+
+    let line = serde_json::to_string(entry).context("Failed to serialize audit entry")?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open audit log {:?}", path))?;
+    writeln!(file, "{}", line).with_context(|| format!("Failed to write audit log {:?}", path))?;
+    Ok(())
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+fn now() -> String {
+    DateTime::now()
+        .try_to_rfc3339_string()
+        .unwrap_or_else(|_| DateTime::now().to_string())
+}
@@ -0,0 +1,134 @@
+use anyhow::Result;
+use futures::stream::TryStreamExt;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::Collection;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+use crate::mongo::{open_source_cursor, CopyOptions, MongoConnection};
+
+/// How many documents are looked up at the destination per round trip
+const LOOKUP_BATCH: usize = 500;
+/// How many `_id`s of each kind of difference are kept for reporting
+const MAX_REPORTED_IDS: usize = 10;
+
+/// Outcome of [`verify_checksums`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChecksumReport {
+    /// Source documents hashed
+    pub checked: u64,
+    /// Destination documents whose hash differs from the source's
+    pub mismatched: u64,
+    /// Source documents with no destination document of the same `_id`
+    pub missing: u64,
+    /// The first few `_id`s of mismatched documents
+    pub mismatched_ids: Vec<Bson>,
+    /// The first few `_id`s of missing documents
+    pub missing_ids: Vec<Bson>,
+}
+
+impl ChecksumReport {
+    pub fn is_match(&self) -> bool {
+        self.mismatched == 0 && self.missing == 0
+    }
+
+    fn record(&mut self, id: Bson, source_sum: &[u8; 32], dest_sum: Option<&[u8; 32]>) {
+        self.checked += 1;
+        match dest_sum {
+            None => {
+                self.missing += 1;
+                if self.missing_ids.len() < MAX_REPORTED_IDS {
+                    self.missing_ids.push(id);
+                }
+            }
+            Some(sum) if sum != source_sum => {
+                self.mismatched += 1;
+                if self.mismatched_ids.len() < MAX_REPORTED_IDS {
+                    self.mismatched_ids.push(id);
+                }
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+/// Hash each source document the copy in `options` read (the first `sample`
+/// of them, if given) and the destination document with the same `_id`,
+/// comparing SHA-256 digests of their BSON
+pub async fn verify_checksums(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    options: &CopyOptions,
+    sample: Option<u64>,
+) -> Result<ChecksumReport> {
+    info!(
+        "Verifying checksums of '{}.{}' against '{}.{}'",
+        options.source_db, options.source_coll, options.dest_db, options.dest_coll
+    );
+    let mut options = options.clone();
+    if let Some(n) = sample {
+        options.limit = Some(options.limit.map_or(n, |limit| limit.min(n)));
+    }
+
+    let source_collection = source
+        .get_database(&options.source_db)
+        .collection::<Document>(&options.source_coll);
+    let dest_collection = dest
+        .get_database(&options.dest_db)
+        .collection::<Document>(&options.dest_coll);
+
+    let mut cursor = open_source_cursor(&source_collection, &options).await?;
+    let mut report = ChecksumReport::default();
+    let mut batch = Vec::new();
+    while let Some(document) = cursor.try_next().await? {
+        let Some(id) = document.get("_id").cloned() else {
+            anyhow::bail!("Checksums match documents by _id, which the projection leaves out");
+        };
+        batch.push((id, checksum(&document)?));
+        if batch.len() >= LOOKUP_BATCH {
+            check_batch(&dest_collection, &mut batch, &mut report).await?;
+        }
+    }
+    if !batch.is_empty() {
+        check_batch(&dest_collection, &mut batch, &mut report).await?;
+    }
+
+    debug!(
+        "Checked {} documents: {} mismatched, {} missing",
+        report.checked, report.mismatched, report.missing
+    );
+    Ok(report)
+}
+
+async fn check_batch(
+    dest_collection: &Collection<Document>,
+    batch: &mut Vec<(Bson, [u8; 32])>,
+    report: &mut ChecksumReport,
+) -> Result<()> {
+    let ids: Vec<Bson> = batch.iter().map(|(id, _)| id.clone()).collect();
+    let mut cursor = dest_collection.find(doc! { "_id": { "$in": ids } }).await?;
+
+    let mut found = HashMap::new();
+    while let Some(document) = cursor.try_next().await? {
+        if let Some(id) = document.get("_id") {
+            found.insert(id_key(id)?, checksum(&document)?);
+        }
+    }
+
+    for (id, sum) in batch.drain(..) {
+        let dest_sum = found.get(&id_key(&id)?);
+        report.record(id, &sum, dest_sum);
+    }
+    Ok(())
+}
+
+fn checksum(document: &Document) -> Result<[u8; 32]> {
+    let bytes = mongodb::bson::to_vec(document)?;
+    Ok(Sha256::digest(bytes).into())
+}
+
+/// `Bson` isn't hashable, so key lookups on the encoded `_id` instead
+fn id_key(id: &Bson) -> Result<Vec<u8>> {
+    Ok(mongodb::bson::to_vec(&doc! { "_id": id.clone() })?)
+}
@@ -5,6 +5,8 @@ use std::path::PathBuf;
 use tracing::debug;
 
 const CONFIG_FILE_NAME: &str = "config.json";
+/// Environment variable overriding the config file location
+pub const CONFIG_PATH_ENV_VAR: &str = "MONGO_COPY_CONFIG";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UriEntry {
@@ -12,14 +14,51 @@ pub struct UriEntry {
     pub uri: String,
 }
 
+/// A named filter and/or projection, used with `--filter-preset`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    /// Find filter as (extended) JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<serde_json::Value>,
+    /// Projection as (extended) JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projection: Option<serde_json::Value>,
+}
+
+/// Choices remembered from the last interactive run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Defaults {
+    /// Copy mode selected last time, e.g. `collections`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copy_mode: Option<String>,
+    /// Last `--max-batch-bytes` given explicitly
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_batch_bytes: Option<u64>,
+}
+
+impl Defaults {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub uris: Vec<UriEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub presets: Vec<Preset>,
+    #[serde(default, skip_serializing_if = "Defaults::is_empty")]
+    pub defaults: Defaults,
 }
 
 impl Config {
     pub fn new() -> Self {
-        Self { uris: Vec::new() }
+        Self {
+            uris: Vec::new(),
+            presets: Vec::new(),
+            defaults: Defaults::default(),
+        }
     }
 
     pub fn load() -> Result<Self> {
@@ -88,7 +127,42 @@ impl Config {
         }
     }
 
-    #[allow(dead_code)]
+    /// Save `preset`, replacing one with the same name
+    pub fn add_preset(&mut self, preset: Preset) -> Result<()> {
+        debug!("Saving preset: {}", preset.name);
+        match self.presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+        self.save()
+    }
+
+    pub fn remove_preset(&mut self, name: &str) -> Result<bool> {
+        debug!("Removing preset: {}", name);
+        let original_len = self.presets.len();
+        self.presets.retain(|p| p.name != name);
+        if self.presets.len() < original_len {
+            self.save()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub fn get_preset(&self, name: &str) -> Option<&Preset> {
+        self.presets.iter().find(|p| p.name == name)
+    }
+
+    /// Replace the remembered defaults and save
+    pub fn set_defaults(&mut self, defaults: Defaults) -> Result<()> {
+        if self.defaults == defaults {
+            return Ok(());
+        }
+        debug!("Saving defaults: {:?}", defaults);
+        self.defaults = defaults;
+        self.save()
+    }
+
     pub fn get_uri(&self, name: &str) -> Option<&str> {
         self.uris
             .iter()
@@ -100,7 +174,11 @@ impl Config {
         self.uris.iter().map(|e| e.name.clone()).collect()
     }
 
-    fn config_path() -> Result<PathBuf> {
+    /// Location of the config file, `$MONGO_COPY_CONFIG` if set
+    pub fn config_path() -> Result<PathBuf> {
+        if let Some(path) = std::env::var_os(CONFIG_PATH_ENV_VAR) {
+            return Ok(PathBuf::from(path));
+        }
         let config_dir = dirs::config_dir().context("Failed to determine config directory")?;
         Ok(config_dir.join("mongo-copy").join(CONFIG_FILE_NAME))
     }
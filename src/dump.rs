@@ -0,0 +1,469 @@
+use anyhow::{Context, Result};
+use futures::stream::{self, TryStreamExt};
+use mongodb::bson::{doc, Bson, Document};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use tracing::{debug, error, info, warn};
+
+use crate::format_bytes;
+use crate::json::parse_document;
+use crate::mongo::{
+    capped_copy_options, check_destination_not_view, copy_documents, create_destination,
+    ensure_ttl_index, is_capped, open_source_cursor, resolve_limit_percent, CopyOptions, CopyStats,
+    MongoConnection,
+};
+use crate::namespace::{case_collisions, check_namespace_length};
+
+/// One collection in a `mongodump` output directory
+#[derive(Debug, Clone)]
+pub struct DumpCollection {
+    pub database: String,
+    pub collection: String,
+    /// `<collection>.bson`, the documents
+    pub bson_path: PathBuf,
+    /// `<collection>.metadata.json`, the options and indexes, if present
+    pub metadata_path: Option<PathBuf>,
+    /// Size of the `.bson` file in bytes
+    pub size: u64,
+}
+
+/// Find the collections in a `mongodump` directory, either a whole dump
+/// (`<dir>/<db>/<coll>.bson`) or a single database's directory
+pub fn scan_dump(dir: &Path) -> Result<Vec<DumpCollection>> {
+    debug!("Scanning dump directory: {:?}", dir);
+    let mut found = scan_database_dir(dir)?;
+
+    if found.is_empty() {
+        let mut entries: Vec<_> = fs::read_dir(dir)
+            .with_context(|| format!("Failed to read dump directory {:?}", dir))?
+            .collect::<io::Result<_>>()?;
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            if entry.file_type()?.is_dir() {
+                found.extend(scan_database_dir(&entry.path())?);
+            }
+        }
+    }
+
+    debug!("Found {} collection(s) in dump", found.len());
+    Ok(found)
+}
+
+fn scan_database_dir(dir: &Path) -> Result<Vec<DumpCollection>> {
+    let database = dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .with_context(|| format!("Dump directory {:?} has no usable name", dir))?
+        .to_string();
+
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read dump directory {:?}", dir))?
+        .collect::<io::Result<_>>()?;
+    entries.sort_by_key(|e| e.file_name());
+
+    let mut found = Vec::new();
+    for entry in entries {
+        let file_name = entry.file_name();
+        let Some(name) = file_name.to_str() else {
+            continue;
+        };
+        if name.ends_with(".bson.gz") {
+            warn!(
+                "Skipping {:?}: compressed dumps are not supported",
+                entry.path()
+            );
+            continue;
+        }
+        let Some(collection) = name.strip_suffix(".bson") else {
+            continue;
+        };
+        // oplog.bson comes from --oplog, system collections are server managed
+        if collection == "oplog" || collection.starts_with("system.") {
+            debug!("Skipping {:?}", entry.path());
+            continue;
+        }
+
+        let metadata_path = dir.join(format!("{}.metadata.json", collection));
+        found.push(DumpCollection {
+            database: database.clone(),
+            collection: collection.to_string(),
+            bson_path: entry.path(),
+            metadata_path: metadata_path.exists().then_some(metadata_path),
+            size: entry.metadata()?.len(),
+        });
+    }
+    Ok(found)
+}
+
+/// Load one dumped collection into `dest`, creating the collection with its
+/// dumped options first and building its dumped indexes afterwards
+pub async fn restore_dump_collection(
+    dest: &MongoConnection,
+    dump: &DumpCollection,
+    options: &CopyOptions,
+) -> Result<CopyStats> {
+    debug!(
+        "Restoring {:?} into '{}.{}'",
+        dump.bson_path, options.dest_db, options.dest_coll
+    );
+    let metadata = match &dump.metadata_path {
+        Some(path) => Some(load_metadata(path)?),
+        None => None,
+    };
+
+    // A dumped view is recreated as a view and has no documents to write
+    check_namespace_length(&options.dest_db, &options.dest_coll)?;
+    let dumped_view = metadata
+        .as_ref()
+        .is_some_and(|metadata| metadata.get_str("type").ok() == Some("view"));
+    if !dumped_view {
+        check_destination_not_view(dest, options).await?;
+    }
+
+    match (&options.dest_collection_options, &metadata) {
+        (Some(create_options), _) => create_destination(dest, options, create_options).await?,
+        (None, Some(metadata)) => create_collection(dest, options, metadata).await?,
+        (None, None) => {}
+    }
+
+    let file = File::open(&dump.bson_path)
+        .with_context(|| format!("Failed to open {:?}", dump.bson_path))?;
+    let mut documents = stream::iter(BsonFileReader::new(file));
+    let dest_collection = dest
+        .get_database(&options.dest_db)
+        .collection::<Document>(&options.dest_coll);
+    if let Some(ttl) = &options.ttl {
+        ensure_ttl_index(&dest_collection, ttl).await?;
+    }
+    let stats = copy_documents(&mut documents, &dest_collection, options, None)
+        .await
+        .with_context(|| format!("Failed to restore {:?}", dump.bson_path))?;
+
+    if let Some(metadata) = &metadata {
+        create_indexes(dest, options, metadata).await?;
+    }
+
+    Ok(stats)
+}
+
+/// Write every collection of `source_db` under `<dir>/<dest_db>/`, skipping
+/// server-managed `system.*` collections
+pub async fn dump_database(
+    source: &MongoConnection,
+    source_db: &str,
+    dest_db: &str,
+    dir: &Path,
+    template: &CopyOptions,
+) -> Result<CopyStats> {
+    let collections: Vec<String> = source
+        .list_collections(source_db)
+        .await?
+        .into_iter()
+        .filter(|c| !c.starts_with("system."))
+        .collect();
+
+    info!(
+        "Dumping database '{}' to {:?}",
+        source_db,
+        dir.join(dest_db)
+    );
+    info!("Found {} collections", collections.len());
+    for group in case_collisions(collections.iter().map(String::as_str)) {
+        warn!(
+            "Collections {} differ only by case; their dump files overwrite each other on a case-insensitive filesystem",
+            group.join(", ")
+        );
+    }
+
+    let mut totals = CopyStats::default();
+    for (idx, collection) in collections.iter().enumerate() {
+        info!(
+            "\nDumping collection '{}' ({}/{})",
+            collection,
+            idx + 1,
+            collections.len()
+        );
+        let options = CopyOptions {
+            source_db: source_db.to_string(),
+            source_coll: collection.clone(),
+            dest_db: dest_db.to_string(),
+            dest_coll: collection.clone(),
+            ..template.clone()
+        };
+        match dump_collection(source, &options, dir).await {
+            Ok(stats) => {
+                info!(
+                    "Dumped {} documents ({}) from '{}'",
+                    stats.documents,
+                    format_bytes(stats.bytes),
+                    collection
+                );
+                totals.add(stats);
+            }
+            Err(e) => {
+                error!("Failed to dump collection '{}': {}", collection, e);
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(totals)
+}
+
+/// Write the collection described by `options` to
+/// `<dir>/<dest_db>/<dest_coll>.bson` plus its `.metadata.json`, in the
+/// layout `mongorestore` expects. Views get only the metadata file.
+pub async fn dump_collection(
+    source: &MongoConnection,
+    options: &CopyOptions,
+    dir: &Path,
+) -> Result<CopyStats> {
+    let db_dir = dir.join(&options.dest_db);
+    fs::create_dir_all(&db_dir).with_context(|| format!("Failed to create {:?}", db_dir))?;
+    let bson_path = db_dir.join(format!("{}.bson", options.dest_coll));
+    let metadata_path = db_dir.join(format!("{}.metadata.json", options.dest_coll));
+    debug!(
+        "Dumping '{}.{}' to {:?}",
+        options.source_db, options.source_coll, bson_path
+    );
+
+    let db = source.get_database(&options.source_db);
+    let spec = source
+        .collection_spec(&options.source_db, &options.source_coll)
+        .await?
+        .with_context(|| {
+            format!(
+                "Source collection '{}.{}' no longer exists",
+                options.source_db, options.source_coll
+            )
+        })?;
+    let is_view = spec.get_str("type").ok() == Some("view");
+    let options = &resolve_limit_percent(source, options).await?;
+
+    let indexes = if is_view {
+        Vec::new()
+    } else {
+        db.collection::<Document>(&options.source_coll)
+            .list_indexes()
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?
+            .into_iter()
+            .map(|index| mongodb::bson::to_bson(&index))
+            .collect::<std::result::Result<Vec<_>, _>>()?
+    };
+
+    let mut metadata = doc! {
+        "options": spec.get_document("options").cloned().unwrap_or_default(),
+        "indexes": indexes,
+        "collectionName": &options.dest_coll,
+        "type": if is_view { "view" } else { "collection" },
+    };
+    if let Ok(info) = spec.get_document("info") {
+        if let Some(uuid) = info.get("uuid") {
+            metadata.insert("uuid", uuid.clone());
+        }
+    }
+    let json = Bson::Document(metadata)
+        .into_canonical_extjson()
+        .to_string();
+    fs::write(&metadata_path, json)
+        .with_context(|| format!("Failed to write {:?}", metadata_path))?;
+
+    if is_view {
+        info!(
+            "'{}.{}' is a view, wrote its definition only",
+            options.source_db, options.source_coll
+        );
+        return Ok(CopyStats::default());
+    }
+
+    let capped_options;
+    let options = if is_capped(&spec) && !options.natural_order {
+        capped_options = capped_copy_options(options);
+        &capped_options
+    } else {
+        options
+    };
+
+    let file =
+        File::create(&bson_path).with_context(|| format!("Failed to create {:?}", bson_path))?;
+    let mut writer = BufWriter::new(file);
+    let source_collection = db.collection::<Document>(&options.source_coll);
+    let mut cursor = open_source_cursor(&source_collection, options).await?;
+
+    let mut stats = CopyStats::default();
+    while let Some(document) = cursor.try_next().await? {
+        let bytes = mongodb::bson::to_vec(&document)?;
+        writer
+            .write_all(&bytes)
+            .with_context(|| format!("Failed to write {:?}", bson_path))?;
+        stats.documents += 1;
+        stats.bytes += bytes.len() as u64;
+        if stats.documents % 1000 == 0 {
+            info!(
+                "  Dumped {} documents ({})...",
+                stats.documents,
+                format_bytes(stats.bytes)
+            );
+        }
+    }
+    writer
+        .flush()
+        .with_context(|| format!("Failed to write {:?}", bson_path))?;
+
+    Ok(stats)
+}
+
+fn load_metadata(path: &Path) -> Result<Document> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    parse_document(&content).with_context(|| format!("Failed to parse {:?}", path))
+}
+
+async fn create_collection(
+    dest: &MongoConnection,
+    options: &CopyOptions,
+    metadata: &Document,
+) -> Result<()> {
+    if dest
+        .collection_exists(&options.dest_db, &options.dest_coll)
+        .await?
+    {
+        debug!(
+            "'{}.{}' already exists, keeping its options",
+            options.dest_db, options.dest_coll
+        );
+        return Ok(());
+    }
+
+    let mut command = doc! { "create": &options.dest_coll };
+    if let Ok(collection_options) = metadata.get_document("options") {
+        command.extend(collection_options.clone());
+    }
+    debug!("Creating collection: {}", command);
+    dest.get_database(&options.dest_db)
+        .run_command(command)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to create '{}.{}' with its dumped options",
+                options.dest_db, options.dest_coll
+            )
+        })?;
+    Ok(())
+}
+
+async fn create_indexes(
+    dest: &MongoConnection,
+    options: &CopyOptions,
+    metadata: &Document,
+) -> Result<()> {
+    let Ok(indexes) = metadata.get_array("indexes") else {
+        return Ok(());
+    };
+
+    let mut specs = Vec::new();
+    let mut built = Vec::new();
+    let mut skipped = Vec::new();
+    for index in indexes.iter().filter_map(Bson::as_document) {
+        let name = index.get_str("name").unwrap_or_default();
+        if name == "_id_" {
+            continue;
+        }
+        let selected = options.indexes.is_empty() || options.indexes.iter().any(|n| n == name);
+        if !selected || options.skip_indexes.iter().any(|n| n == name) {
+            skipped.push(name);
+            continue;
+        }
+        // Older dumps carry the source namespace, which newer servers reject
+        let mut index = index.clone();
+        index.remove("ns");
+        specs.push(Bson::Document(index));
+        built.push(name);
+    }
+    for name in options.indexes.iter().chain(&options.skip_indexes) {
+        if !built.contains(&name.as_str()) && !skipped.contains(&name.as_str()) {
+            warn!(
+                "No index named '{}' in the dump of '{}.{}'",
+                name, options.dest_db, options.dest_coll
+            );
+        }
+    }
+    if !skipped.is_empty() {
+        info!(
+            "Skipping index(es) {} on '{}.{}'",
+            skipped.join(", "),
+            options.dest_db,
+            options.dest_coll
+        );
+    }
+    if specs.is_empty() {
+        return Ok(());
+    }
+
+    info!(
+        "Building index(es) {} on '{}.{}'",
+        built.join(", "),
+        options.dest_db,
+        options.dest_coll
+    );
+    dest.get_database(&options.dest_db)
+        .run_command(doc! { "createIndexes": &options.dest_coll, "indexes": specs })
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to build dumped indexes on '{}.{}'",
+                options.dest_db, options.dest_coll
+            )
+        })?;
+    Ok(())
+}
+
+/// Reads the length-prefixed BSON documents of a `.bson` dump file one at a time
+struct BsonFileReader {
+    reader: BufReader<File>,
+}
+
+impl BsonFileReader {
+    fn new(file: File) -> Self {
+        Self {
+            reader: BufReader::new(file),
+        }
+    }
+
+    fn read_document(&mut self) -> io::Result<Option<Document>> {
+        let mut len_bytes = [0u8; 4];
+        let mut read = 0;
+        while read < len_bytes.len() {
+            match self.reader.read(&mut len_bytes[read..])? {
+                0 if read == 0 => return Ok(None),
+                0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                n => read += n,
+            }
+        }
+
+        let len = i32::from_le_bytes(len_bytes);
+        if len < 5 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid BSON document length {}", len),
+            ));
+        }
+        let mut bytes = vec![0u8; len as usize];
+        bytes[..4].copy_from_slice(&len_bytes);
+        self.reader.read_exact(&mut bytes[4..])?;
+
+        Document::from_reader(bytes.as_slice())
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl Iterator for BsonFileReader {
+    type Item = io::Result<Document>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_document().transpose()
+    }
+}
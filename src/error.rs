@@ -0,0 +1,115 @@
+use crate::mongo::{
+    is_authentication_error, is_duplicate_key, is_namespace_not_found, is_out_of_space,
+    is_unreachable, Stopped,
+};
+
+/// Why a copy or connection failed, so callers can handle some failures
+/// differently. Every variant keeps the full error with its context, and
+/// displays exactly like it.
+#[derive(Debug, thiserror::Error)]
+pub enum CopyError {
+    /// The server rejected the credentials
+    #[error(transparent)]
+    Authentication(anyhow::Error),
+    /// No server could be reached, or none of them was usable
+    #[error(transparent)]
+    Unreachable(anyhow::Error),
+    /// A database or collection the copy needed doesn't exist
+    #[error(transparent)]
+    NamespaceNotFound(anyhow::Error),
+    /// A write hit a unique index at the destination (E11000)
+    #[error(transparent)]
+    DuplicateKey(anyhow::Error),
+    /// The destination namespace is a view, which can't be written to
+    #[error(transparent)]
+    DestinationIsView(anyhow::Error),
+    /// The destination ran out of disk space or hit a storage quota
+    #[error(transparent)]
+    OutOfSpace(anyhow::Error),
+    /// The copy's [`StopSignal`](crate::StopSignal) was raised
+    #[error(transparent)]
+    Stopped(anyhow::Error),
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl CopyError {
+    /// The error with its context, e.g. to look for a
+    /// [`PartialCopy`](crate::PartialCopy)
+    pub fn inner(&self) -> &anyhow::Error {
+        match self {
+            CopyError::Authentication(e)
+            | CopyError::Unreachable(e)
+            | CopyError::NamespaceNotFound(e)
+            | CopyError::DuplicateKey(e)
+            | CopyError::DestinationIsView(e)
+            | CopyError::OutOfSpace(e)
+            | CopyError::Stopped(e)
+            | CopyError::Other(e) => e,
+        }
+    }
+
+    pub fn into_inner(self) -> anyhow::Error {
+        match self {
+            CopyError::Authentication(e)
+            | CopyError::Unreachable(e)
+            | CopyError::NamespaceNotFound(e)
+            | CopyError::DuplicateKey(e)
+            | CopyError::DestinationIsView(e)
+            | CopyError::OutOfSpace(e)
+            | CopyError::Stopped(e)
+            | CopyError::Other(e) => e,
+        }
+    }
+
+    /// Constructor of the variant `self` is, to put another error in the
+    /// same category
+    fn variant(&self) -> fn(anyhow::Error) -> CopyError {
+        match self {
+            CopyError::Authentication(_) => CopyError::Authentication,
+            CopyError::Unreachable(_) => CopyError::Unreachable,
+            CopyError::NamespaceNotFound(_) => CopyError::NamespaceNotFound,
+            CopyError::DuplicateKey(_) => CopyError::DuplicateKey,
+            CopyError::DestinationIsView(_) => CopyError::DestinationIsView,
+            CopyError::OutOfSpace(_) => CopyError::OutOfSpace,
+            CopyError::Stopped(_) => CopyError::Stopped,
+            CopyError::Other(_) => CopyError::Other,
+        }
+    }
+}
+
+/// Sort an error into a category by what caused it
+impl From<anyhow::Error> for CopyError {
+    fn from(mut error: anyhow::Error) -> Self {
+        // Classified already, e.g. by a nested copy. Only unwrap it when
+        // nothing was added on top, downcasting would drop that context.
+        if error.chain().next().is_some_and(|e| e.is::<CopyError>()) {
+            match error.downcast::<CopyError>() {
+                Ok(copy_error) => return copy_error,
+                Err(e) => error = e,
+            }
+        }
+        let classified = error
+            .chain()
+            .find_map(|e| e.downcast_ref::<CopyError>())
+            .map(CopyError::variant);
+        if let Some(variant) = classified {
+            return variant(error);
+        }
+        if error.chain().any(|e| e.is::<Stopped>()) {
+            return CopyError::Stopped(error);
+        }
+
+        let cause = error
+            .chain()
+            .find_map(|e| e.downcast_ref::<mongodb::error::Error>());
+        match cause {
+            Some(e) if is_authentication_error(e) => CopyError::Authentication(error),
+            Some(e) if is_unreachable(e) => CopyError::Unreachable(error),
+            Some(e) if is_namespace_not_found(e) => CopyError::NamespaceNotFound(error),
+            Some(e) if is_duplicate_key(e) => CopyError::DuplicateKey(error),
+            Some(e) if is_out_of_space(e) => CopyError::OutOfSpace(error),
+            _ => CopyError::Other(error),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use mongo_copy::Stopped;
+use std::fmt;
+
+/// Process exit codes, so scripts can tell outcomes apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// Every selected operation completed
+    Success = 0,
+    /// A copy (or any other operation) failed
+    Failure = 1,
+    /// Nothing failed, but at least one operation was skipped or stopped
+    Partial = 2,
+    /// Could not connect to the source or destination
+    ConnectionFailed = 3,
+    /// Bad arguments, env vars or job file
+    InvalidArgs = 4,
+}
+
+impl ExitStatus {
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    /// Pick the exit status for an error from its marker in the chain
+    pub fn for_error(error: &anyhow::Error) -> Self {
+        if error.downcast_ref::<ConnectionFailed>().is_some() {
+            ExitStatus::ConnectionFailed
+        } else if error.downcast_ref::<InvalidArgs>().is_some() {
+            ExitStatus::InvalidArgs
+        } else if error.downcast_ref::<Stopped>().is_some() {
+            ExitStatus::Partial
+        } else {
+            ExitStatus::Failure
+        }
+    }
+}
+
+/// Marks an error as a failure to connect to one side of the copy
+#[derive(Debug)]
+pub struct ConnectionFailed(pub &'static str);
+
+impl fmt::Display for ConnectionFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to connect to {} MongoDB", self.0)
+    }
+}
+
+impl std::error::Error for ConnectionFailed {}
+
+/// Marks an error as caused by invalid user input rather than the copy itself
+#[derive(Debug)]
+pub struct InvalidArgs(pub String);
+
+impl fmt::Display for InvalidArgs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidArgs {}
@@ -0,0 +1,141 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+use mongo_copy::config::Config;
+use mongo_copy::format_bytes;
+
+use crate::audit::AuditEntry;
+use crate::list::OutputFormat;
+
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+/// The history is rotated to `history.jsonl.1` once it grows past this, so
+/// at most about twice this is kept
+const MAX_HISTORY_BYTES: u64 = 5 * 1024 * 1024;
+
+#[derive(Args)]
+pub struct HistoryArgs {
+    /// How many of the most recent operations to show
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    limit: usize,
+
+    /// How to print the history
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+}
+
+/// The fields of a recorded [`AuditEntry`] shown by `mongo-copy history`
+#[derive(Debug, Deserialize, Serialize)]
+struct HistoryEntry {
+    operation: String,
+    started_at: String,
+    finished_at: Option<String>,
+    source: String,
+    destination: String,
+    source_namespace: String,
+    destination_namespace: String,
+    documents: u64,
+    bytes: u64,
+    outcome: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Location of the operation history, next to the config file
+fn history_path() -> Result<PathBuf> {
+    let config_path = Config::config_path()?;
+    let dir = config_path
+        .parent()
+        .context("Config path has no parent directory")?;
+    Ok(dir.join(HISTORY_FILE_NAME))
+}
+
+fn rotated_path(path: &std::path::Path) -> PathBuf {
+    path.with_extension("jsonl.1")
+}
+
+/// Append `entry` to the operation history, rotating it first if it has
+/// grown too large. Failures are only logged, never failing the copy.
+pub fn append(entry: &AuditEntry) {
+    if let Err(e) = try_append(entry) {
+        warn!("Failed to record operation history: {:#}", e);
+    }
+}
+
+fn try_append(entry: &AuditEntry) -> Result<()> {
+    let path = history_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context("Failed to create config directory")?;
+    }
+
+    if fs::metadata(&path).is_ok_and(|meta| meta.len() > MAX_HISTORY_BYTES) {
+        debug!("Rotating operation history at {:?}", path);
+        fs::rename(&path, rotated_path(&path))
+            .with_context(|| format!("Failed to rotate {:?}", path))?;
+    }
+
+    debug!("Writing operation history to: {:?}", path);
+    let line = serde_json::to_string(entry).context("Failed to serialize history entry")?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {:?}", path))?;
+    writeln!(file, "{}", line).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(())
+}
+
+/// Oldest first, across the rotated and current files
+fn load() -> Result<Vec<HistoryEntry>> {
+    let path = history_path()?;
+    let mut entries = Vec::new();
+    for file in [rotated_path(&path), path] {
+        let content = match fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", file)),
+        };
+        for (idx, line) in content.lines().enumerate() {
+            match serde_json::from_str(line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => debug!("Skipping line {} of {:?}: {}", idx + 1, file, e),
+            }
+        }
+    }
+    Ok(entries)
+}
+
+pub fn run(args: &HistoryArgs) -> Result<()> {
+    let entries = load()?;
+    let recent = &entries[entries.len().saturating_sub(args.limit)..];
+
+    match args.output {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(recent)?),
+        OutputFormat::Text if recent.is_empty() => {
+            println!("No operations recorded yet ({:?})", history_path()?)
+        }
+        OutputFormat::Text => {
+            for entry in recent {
+                println!(
+                    "{}  {:<7}  {:<10}  {} -> {}  {} documents ({})",
+                    entry.started_at,
+                    entry.outcome,
+                    entry.operation,
+                    entry.source_namespace,
+                    entry.destination_namespace,
+                    entry.documents,
+                    format_bytes(entry.bytes)
+                );
+                println!("    {} -> {}", entry.source, entry.destination);
+                if let Some(error) = &entry.error {
+                    println!("    error: {}", error);
+                }
+            }
+        }
+    }
+    Ok(())
+}
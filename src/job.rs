@@ -0,0 +1,389 @@
+use anyhow::{Context, Result, bail};
+use mongodb::bson::Document;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use tracing::{debug, error, info};
+
+use crate::Cli;
+use crate::audit::{self, AuditEntry};
+use crate::exit::{ConnectionFailed, InvalidArgs};
+use crate::schema::{validate_job_file, yaml_line};
+use crate::ui::{Session, UriOrigin};
+use mongo_copy::json::to_document;
+use mongo_copy::keystore::KeyStore;
+use mongo_copy::mongo::validate_projection;
+use mongo_copy::namespace::{is_system_database, parse_namespace};
+use mongo_copy::{MongoConnection, WriteStrategy, copy_collection, format_bytes, mask_uri};
+
+/// A reproducible migration described in a YAML file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobFile {
+    pub source: Endpoint,
+    pub destination: Endpoint,
+    pub jobs: Vec<Job>,
+}
+
+/// Either a saved URI name or an inline connection string
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Endpoint {
+    pub name: Option<String>,
+    pub uri: Option<String>,
+}
+
+/// A single namespace mapping within a job file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    /// Source namespace (`database.collection`)
+    pub from: String,
+    /// Destination namespace, defaults to `from`
+    pub to: Option<String>,
+    pub limit: Option<u64>,
+    /// Find filter as (extended) JSON
+    pub filter: Option<serde_json::Value>,
+    /// Find projection as (extended) JSON
+    pub projection: Option<serde_json::Value>,
+    #[serde(default)]
+    pub write: WriteStrategy,
+    /// Fields matching upserted documents instead of `_id`
+    #[serde(default)]
+    pub upsert_key: Vec<String>,
+}
+
+/// A validated job, ready to execute
+struct PlannedJob {
+    source_db: String,
+    source_coll: String,
+    dest_db: String,
+    dest_coll: String,
+    limit: Option<u64>,
+    filter: Option<Document>,
+    projection: Option<Document>,
+    write: WriteStrategy,
+    upsert_key: Vec<String>,
+}
+
+impl JobFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        debug!("Loading job file from: {:?}", path);
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read job file {:?}", path))?;
+        // Parsed generically first, so schema violations are reported by field
+        // rather than as the first serde error
+        let value: serde_json::Value = serde_yaml::from_str(&content)
+            .map_err(|e| InvalidArgs(format!("Failed to parse job file {:?}: {}", path, e)))?;
+        let violations = validate_job_file(&value);
+        if !violations.is_empty() {
+            let errors: Vec<String> = violations
+                .iter()
+                .map(|violation| match yaml_line(&content, &violation.field) {
+                    Some(line) => format!("line {}: {}", line, violation),
+                    None => violation.to_string(),
+                })
+                .collect();
+            return Err(InvalidArgs(format!(
+                "Job file {:?} does not match the schema:\n  - {}",
+                path,
+                errors.join("\n  - ")
+            ))
+            .into());
+        }
+        let job_file: JobFile = serde_json::from_value(value)
+            .map_err(|e| InvalidArgs(format!("Failed to parse job file {:?}: {}", path, e)))?;
+        debug!("Loaded job file with {} job(s)", job_file.jobs.len());
+        Ok(job_file)
+    }
+
+    /// Check the whole file, reporting every problem at once
+    fn plan(&self) -> Result<Vec<PlannedJob>> {
+        let mut errors = Vec::new();
+
+        for (label, endpoint) in [("source", &self.source), ("destination", &self.destination)] {
+            if let Err(e) = endpoint.validate() {
+                errors.push(format!("{}: {}", label, e));
+            }
+        }
+
+        if self.jobs.is_empty() {
+            errors.push("no jobs defined".to_string());
+        }
+
+        let mut planned = Vec::new();
+        let mut destinations = HashSet::new();
+        for (idx, job) in self.jobs.iter().enumerate() {
+            match job.plan() {
+                Ok(p) => {
+                    if !destinations.insert(format!("{}.{}", p.dest_db, p.dest_coll)) {
+                        errors.push(format!(
+                            "job {}: destination '{}.{}' is used by more than one job",
+                            idx + 1,
+                            p.dest_db,
+                            p.dest_coll
+                        ));
+                    }
+                    planned.push(p);
+                }
+                Err(job_errors) => {
+                    errors.extend(
+                        job_errors
+                            .into_iter()
+                            .map(|e| format!("job {}: {}", idx + 1, e)),
+                    );
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(
+                InvalidArgs(format!("Invalid job file:\n  - {}", errors.join("\n  - "))).into(),
+            );
+        }
+
+        Ok(planned)
+    }
+}
+
+impl Endpoint {
+    fn validate(&self) -> Result<()> {
+        match (&self.name, &self.uri) {
+            (Some(_), Some(_)) => bail!("specify either `name` or `uri`, not both"),
+            (None, None) => bail!("one of `name` or `uri` is required"),
+            (Some(name), None) if name.trim().is_empty() => bail!("`name` is empty"),
+            (None, Some(uri))
+                if !uri.starts_with("mongodb://") && !uri.starts_with("mongodb+srv://") =>
+            {
+                bail!("`uri` must start with mongodb:// or mongodb+srv://")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn origin(&self) -> UriOrigin {
+        match &self.name {
+            Some(name) => UriOrigin::Saved(name.clone()),
+            None => UriOrigin::JobFile,
+        }
+    }
+
+    fn resolve(&self, keystore: &KeyStore) -> Result<String> {
+        if let Some(uri) = &self.uri {
+            return Ok(uri.clone());
+        }
+        let name = self.name.as_deref().unwrap_or_default();
+        debug!("Resolving saved URI: {}", name);
+        keystore.get_uri(name)?.ok_or_else(|| {
+            InvalidArgs(format!("No saved URI named '{}' found in keyring", name)).into()
+        })
+    }
+}
+
+impl Job {
+    fn plan(&self) -> std::result::Result<PlannedJob, Vec<String>> {
+        let mut errors = Vec::new();
+
+        let source = parse_namespace(&self.from).map_err(|e| errors.push(e.to_string()));
+        let dest = match &self.to {
+            Some(to) => parse_namespace(to).map_err(|e| errors.push(e.to_string())),
+            None => source.clone(),
+        };
+
+        if let Ok((dest_db, _)) = &dest {
+            if is_system_database(dest_db) {
+                errors.push(format!(
+                    "destination database '{}' is a reserved system database",
+                    dest_db
+                ));
+            }
+        }
+
+        if self.limit == Some(0) {
+            errors.push("`limit` must be greater than 0".to_string());
+        }
+
+        let filter = match &self.filter {
+            Some(value) => to_document(value)
+                .map(Some)
+                .map_err(|e| errors.push(format!("invalid `filter`: {}", e))),
+            None => Ok(None),
+        };
+
+        if !self.upsert_key.is_empty() && self.write != WriteStrategy::Upsert {
+            errors.push("`upsert_key` requires `write: upsert`".to_string());
+        }
+        for field in &self.upsert_key {
+            if field.is_empty() || field.starts_with('$') || field.split('.').any(str::is_empty) {
+                errors.push(format!(
+                    "`upsert_key` field '{}' is not a valid field path",
+                    field
+                ));
+            }
+        }
+
+        let projection = match &self.projection {
+            Some(value) => to_document(value)
+                .and_then(|p| validate_projection(&p).map(|_| p))
+                .map(Some)
+                .map_err(|e| errors.push(format!("invalid `projection`: {}", e))),
+            None => Ok(None),
+        };
+
+        match (source, dest, filter, projection) {
+            (
+                Ok((source_db, source_coll)),
+                Ok((dest_db, dest_coll)),
+                Ok(filter),
+                Ok(projection),
+            ) if errors.is_empty() => Ok(PlannedJob {
+                source_db,
+                source_coll,
+                dest_db,
+                dest_coll,
+                limit: self.limit,
+                filter,
+                projection,
+                write: self.write,
+                upsert_key: self.upsert_key.clone(),
+            }),
+            _ => Err(errors),
+        }
+    }
+}
+
+/// Check a job file without connecting to anything
+pub fn validate(path: &Path) -> Result<()> {
+    let job_file = JobFile::load(path)?;
+    let jobs = job_file.plan()?;
+    println!("{}: valid, {} job(s)", path.display(), jobs.len());
+    Ok(())
+}
+
+pub async fn run(path: &Path, cli: &Cli) -> Result<()> {
+    let job_file = JobFile::load(path)?;
+    let jobs = job_file.plan()?;
+
+    let keystore = cli.keystore();
+    let source_uri = job_file.source.resolve(&keystore)?;
+    let dest_uri = job_file.destination.resolve(&keystore)?;
+    crate::check_plaintext(cli, "source", &source_uri, false)?;
+    crate::check_plaintext(cli, "destination", &dest_uri, false)?;
+
+    println!("\n{}", "=".repeat(80));
+    println!("JOB PLAN");
+    println!("{}", "=".repeat(80));
+    println!("Source:      {}", mask_uri(&source_uri));
+    println!("Destination: {}", mask_uri(&dest_uri));
+    for (idx, job) in jobs.iter().enumerate() {
+        println!(
+            "{:>3}. {}.{} -> {}.{}",
+            idx + 1,
+            job.source_db,
+            job.source_coll,
+            job.dest_db,
+            job.dest_coll
+        );
+        if let Some(limit) = job.limit {
+            println!("       limit:  {}", limit);
+        }
+        if let Some(filter) = &job.filter {
+            println!("       filter: {}", filter);
+        }
+        if let Some(projection) = &job.projection {
+            println!("       projection: {}", projection);
+        }
+        println!("       write:  {:?}", job.write);
+        if !job.upsert_key.is_empty() {
+            println!("       upsert key: {}", job.upsert_key.join(", "));
+        }
+    }
+    println!("{}", "=".repeat(80));
+
+    if cli.dry_run {
+        info!("Dry run: no documents were copied");
+        return Ok(());
+    }
+
+    info!("Connecting to MongoDB instances...");
+    let source = MongoConnection::connect_with_retries(
+        &source_uri,
+        cli.connect_retries,
+        crate::connect_options(cli, true),
+    )
+    .await
+    .map_err(|e| e.into_inner().context(ConnectionFailed("source")))?;
+    let dest = MongoConnection::connect_with_retries(
+        &dest_uri,
+        cli.connect_retries,
+        crate::connect_options(cli, false),
+    )
+    .await
+    .map_err(|e| e.into_inner().context(ConnectionFailed("destination")))?;
+    info!("Connected successfully");
+
+    // A mistyped `from` fails here rather than copying nothing
+    for job in &jobs {
+        crate::ensure_source_collection(&source, &job.source_db, &job.source_coll).await?;
+    }
+
+    let session = Session::new(job_file.source.origin(), job_file.destination.origin());
+    for (idx, job) in jobs.into_iter().enumerate() {
+        info!(
+            "Running job {}: '{}.{}' -> '{}.{}'",
+            idx + 1,
+            job.source_db,
+            job.source_coll,
+            job.dest_db,
+            job.dest_coll
+        );
+        // The job's own selection and write strategy replace the matching flags
+        let options = crate::copy_options(
+            cli,
+            &job.source_db,
+            &job.source_coll,
+            &job.dest_db,
+            &job.dest_coll,
+        )
+        .with_limit(job.limit)
+        .with_limit_percent(None)
+        .with_filter(job.filter.clone())
+        .with_projection(job.projection.clone())
+        .with_write_strategy(job.write)
+        .with_upsert_key(job.upsert_key.clone())
+        .with_stop(Some(session.stop.clone()));
+        let entry = AuditEntry::start(
+            "job",
+            &source.uri,
+            &dest.uri,
+            &format!("{}.{}", job.source_db, job.source_coll),
+            &format!("{}.{}", job.dest_db, job.dest_coll),
+        );
+        let stop_key = session.listen_for_stop();
+        let result = copy_collection(&source, &dest, &options)
+            .await
+            .map_err(crate::copy_failure);
+        drop(stop_key);
+        audit::record(cli, &entry.finish(&result))?;
+        match result {
+            Ok(stats) => {
+                info!(
+                    "Copied {} documents ({}) from '{}.{}' to '{}.{}'",
+                    stats.documents,
+                    format_bytes(stats.bytes),
+                    job.source_db,
+                    job.source_coll,
+                    job.dest_db,
+                    job.dest_coll
+                );
+            }
+            Err(e) => {
+                error!("Job {} failed: {:#}", idx + 1, e);
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use anyhow::{bail, Context, Result};
+use mongodb::bson::{Bson, Document};
+
+/// Parse an (extended) JSON object, e.g. `{"_id": {"$oid": "..."}}`, into a BSON document
+pub fn parse_document(json: &str) -> Result<Document> {
+    let value: serde_json::Value = serde_json::from_str(json).context("Invalid JSON")?;
+    to_document(&value)
+}
+
+/// Convert an already-parsed (extended) JSON value into a BSON document
+pub fn to_document(value: &serde_json::Value) -> Result<Document> {
+    match Bson::try_from(value.clone()) {
+        Ok(Bson::Document(doc)) => Ok(doc),
+        Ok(_) => bail!("expected a JSON object"),
+        Err(e) => bail!("invalid extended JSON: {}", e),
+    }
+}
@@ -2,30 +2,77 @@ use anyhow::{Context, Result};
 use keyring::Entry;
 use tracing::{debug, error, warn};
 
+use crate::config::Config;
+
 const SERVICE_NAME: &str = "mongo-copy";
+/// Environment variable overriding the keyring service name, to keep
+/// separate installs or test runs from sharing entries
+const SERVICE_ENV_VAR: &str = "MONGO_COPY_KEYRING_SERVICE";
+/// Keyring entry holding the names of all stored URIs, since not every
+/// keyring backend can enumerate its entries
+const INDEX_ENTRY: &str = "__mongo-copy-index__";
 
-pub struct KeyStore;
+/// URIs saved in the system keyring under one service name
+pub struct KeyStore {
+    service: String,
+}
+
+/// Differences between the names in the config and the keyring
+#[derive(Debug, Default)]
+pub struct Drift {
+    /// Config names with no URI stored in the keyring
+    pub missing_from_keyring: Vec<String>,
+    /// Keyring entries with no matching config name
+    pub missing_from_config: Vec<String>,
+}
+
+impl Drift {
+    pub fn is_empty(&self) -> bool {
+        self.missing_from_keyring.is_empty() && self.missing_from_config.is_empty()
+    }
+}
 
 impl KeyStore {
+    /// A keystore using `service`, else `$MONGO_COPY_KEYRING_SERVICE`, else
+    /// the default `mongo-copy`
+    pub fn new(service: Option<&str>) -> Self {
+        let service = service
+            .map(str::to_string)
+            .or_else(|| std::env::var(SERVICE_ENV_VAR).ok())
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| SERVICE_NAME.to_string());
+        Self { service }
+    }
+
     /// Store a URI securely in the system keyring
-    pub fn store_uri(name: &str, uri: &str) -> Result<()> {
+    pub fn store_uri(&self, name: &str, uri: &str) -> Result<()> {
         debug!("Storing URI in keyring for: {}", name);
 
-        let entry = Entry::new(SERVICE_NAME, name).context("Failed to create keyring entry")?;
+        if name == INDEX_ENTRY {
+            anyhow::bail!("'{}' is a reserved name", name);
+        }
+
+        let entry = Entry::new(&self.service, name).context("Failed to create keyring entry")?;
 
         entry
             .set_password(uri)
             .context("Failed to store URI in keyring")?;
 
+        let mut names = self.list_names()?;
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+            self.write_index(&names)?;
+        }
+
         debug!("URI stored successfully in keyring: {}", name);
         Ok(())
     }
 
     /// Retrieve a URI from the system keyring
-    pub fn get_uri(name: &str) -> Result<Option<String>> {
+    pub fn get_uri(&self, name: &str) -> Result<Option<String>> {
         debug!("Retrieving URI from keyring for: {}", name);
 
-        let entry = Entry::new(SERVICE_NAME, name).context("Failed to create keyring entry")?;
+        let entry = Entry::new(&self.service, name).context("Failed to create keyring entry")?;
 
         match entry.get_password() {
             Ok(uri) => {
@@ -44,10 +91,16 @@ impl KeyStore {
     }
 
     /// Delete a URI from the system keyring
-    pub fn delete_uri(name: &str) -> Result<bool> {
+    pub fn delete_uri(&self, name: &str) -> Result<bool> {
         debug!("Deleting URI from keyring for: {}", name);
 
-        let entry = Entry::new(SERVICE_NAME, name).context("Failed to create keyring entry")?;
+        let entry = Entry::new(&self.service, name).context("Failed to create keyring entry")?;
+
+        let mut names = self.list_names()?;
+        if names.iter().any(|n| n == name) {
+            names.retain(|n| n != name);
+            self.write_index(&names)?;
+        }
 
         match entry.delete_credential() {
             Ok(_) => {
@@ -66,13 +119,70 @@ impl KeyStore {
     }
 
     /// Check if a URI exists in the keyring
-    #[allow(dead_code)]
-    pub fn has_uri(name: &str) -> bool {
-        let entry = match Entry::new(SERVICE_NAME, name) {
+    pub fn has_uri(&self, name: &str) -> bool {
+        let entry = match Entry::new(&self.service, name) {
             Ok(e) => e,
             Err(_) => return false,
         };
 
         entry.get_password().is_ok()
     }
+
+    /// Whether `error` means the keyring itself can't be used (locked,
+    /// access denied, no backend), as opposed to a missing or bad entry
+    pub fn is_unavailable(error: &anyhow::Error) -> bool {
+        error.chain().any(|cause| {
+            matches!(
+                cause.downcast_ref::<keyring::Error>(),
+                Some(keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_))
+            )
+        })
+    }
+
+    /// List the names recorded in the keyring index
+    pub fn list_names(&self) -> Result<Vec<String>> {
+        let entry =
+            Entry::new(&self.service, INDEX_ENTRY).context("Failed to create keyring entry")?;
+
+        match entry.get_password() {
+            Ok(content) => serde_json::from_str(&content).context("Failed to parse keyring index"),
+            Err(keyring::Error::NoEntry) => Ok(Vec::new()),
+            Err(e) => Err(e).context("Failed to read keyring index"),
+        }
+    }
+
+    /// Compare the config names against what is actually stored in the keyring
+    pub fn reconcile(&self, config: &Config) -> Result<Drift> {
+        let config_names = config.list_names();
+        let mut drift = Drift::default();
+
+        for name in &config_names {
+            if self.get_uri(name)?.is_none() {
+                drift.missing_from_keyring.push(name.clone());
+            }
+        }
+
+        for name in self.list_names()? {
+            if !config_names.contains(&name) && self.get_uri(&name)?.is_some() {
+                drift.missing_from_config.push(name);
+            }
+        }
+
+        debug!(
+            "Reconciled keyring: {} missing from keyring, {} missing from config",
+            drift.missing_from_keyring.len(),
+            drift.missing_from_config.len()
+        );
+        Ok(drift)
+    }
+
+    fn write_index(&self, names: &[String]) -> Result<()> {
+        debug!("Updating keyring index with {} name(s)", names.len());
+        let entry =
+            Entry::new(&self.service, INDEX_ENTRY).context("Failed to create keyring entry")?;
+        let content = serde_json::to_string(names).context("Failed to serialize keyring index")?;
+        entry
+            .set_password(&content)
+            .context("Failed to update keyring index")
+    }
 }
@@ -0,0 +1,62 @@
+//! Copy MongoDB databases and collections between instances.
+//!
+//! This crate contains the copy engine used by the `mongo-copy` CLI. It has no
+//! dependency on the interactive prompts or argument parsing, so it can be
+//! embedded in other tools:
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! use mongo_copy::{copy_collection, CopyOptions, MongoConnection, WriteStrategy};
+//!
+//! let source = MongoConnection::new("mongodb://localhost:27017").await?;
+//! let dest = MongoConnection::new("mongodb://localhost:27018").await?;
+//! let options = CopyOptions::new("app", "users", "app_copy", "users")
+//!     .with_limit(Some(1000))
+//!     .with_write_strategy(WriteStrategy::Upsert);
+//! let copied = copy_collection(&source, &dest, &options).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod checksum;
+pub mod config;
+pub mod dump;
+pub mod error;
+pub mod json;
+pub mod keystore;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod mongo;
+pub mod namespace;
+pub mod plan;
+pub mod resume;
+pub mod throttle;
+pub mod uri;
+
+pub use error::CopyError;
+pub use mongo::{
+    compare_collections, copy_collection, copy_collections, copy_database, copy_gridfs_bucket,
+    percent_limit, rank_collections, resolve_limit_percent, sample_documents, CollectionComparison,
+    CollectionSlice, CollectionStats, ConflictPolicy, ConnectOptions, CopyOptions, CopyResult,
+    CopyStats, CopyStatus, MongoConnection, OversizedPolicy, PartialCopy, ReadConcern, SinceFilter,
+    StopSignal, Stopped, TtlIndex, WriteStrategy,
+};
+pub use plan::{build_plan, CopyPlan};
+pub use resume::ResumeState;
+pub use uri::mask_uri;
+
+/// Format a byte count using binary units, e.g. `1.5 MiB`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
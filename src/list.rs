@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use tracing::{debug, info};
+
+use mongo_copy::{MongoConnection, format_bytes, mask_uri};
+
+use crate::Cli;
+use crate::exit::ConnectionFailed;
+use crate::ui::get_mongodb_uri;
+
+#[derive(Args)]
+pub struct ListArgs {
+    /// Saved URI name or MongoDB URI to inspect (defaults to MONGODB_URI_SOURCE or a prompt)
+    #[arg(long)]
+    source: Option<String>,
+
+    /// How to print the listing
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Serialize)]
+struct DatabaseListing {
+    name: String,
+    collections: Vec<CollectionListing>,
+}
+
+#[derive(Serialize)]
+struct CollectionListing {
+    name: String,
+    documents: u64,
+    size: u64,
+    storage_size: u64,
+}
+
+pub async fn run(args: &ListArgs, cli: &Cli) -> Result<()> {
+    let uri = resolve_source(args.source.as_deref(), cli)?;
+    crate::check_plaintext(cli, "source", &uri, true)?;
+    info!("Connecting to {}", mask_uri(&uri));
+    let conn = MongoConnection::connect_with_retries(
+        &uri,
+        cli.connect_retries,
+        crate::connect_options(cli, false),
+    )
+    .await
+    .map_err(|e| e.into_inner().context(ConnectionFailed("source")))?;
+
+    let mut listing = Vec::new();
+    for database in conn.list_databases().await? {
+        let mut collections = Vec::new();
+        for name in conn.list_collections(&database).await? {
+            let documents = conn.get_collection_count(&database, &name).await?;
+            // Views and some system collections don't support collStats
+            let stats = match conn.collection_stats(&database, &name).await {
+                Ok(stats) => stats,
+                Err(e) => {
+                    debug!("No stats for '{}.{}': {:#}", database, name, e);
+                    Default::default()
+                }
+            };
+            collections.push(CollectionListing {
+                name,
+                documents,
+                size: stats.size,
+                storage_size: stats.storage_size,
+            });
+        }
+        listing.push(DatabaseListing {
+            name: database,
+            collections,
+        });
+    }
+
+    match args.output {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&listing)?),
+        OutputFormat::Text => print_tree(&listing),
+    }
+    Ok(())
+}
+
+/// A saved name is looked up in the keyring, anything that looks like a URI is used as is
+fn resolve_source(source: Option<&str>, cli: &Cli) -> Result<String> {
+    let keystore = cli.keystore();
+    match source {
+        Some(uri) if uri.starts_with("mongodb://") || uri.starts_with("mongodb+srv://") => {
+            Ok(uri.to_string())
+        }
+        Some(name) => keystore
+            .get_uri(name)?
+            .with_context(|| format!("No saved URI named '{}' found in keyring", name)),
+        None => {
+            let env_var = crate::uri_env_var(cli.source_env.as_deref(), "MONGODB_URI_SOURCE")?;
+            let (uri, _) = get_mongodb_uri(
+                &keystore,
+                &env_var,
+                "Select or enter MongoDB URI:",
+                cli.skip_env,
+            )?;
+            Ok(uri)
+        }
+    }
+}
+
+fn print_tree(listing: &[DatabaseListing]) {
+    for database in listing {
+        let total: u64 = database.collections.iter().map(|c| c.size).sum();
+        println!(
+            "{} ({} collection(s), {})",
+            database.name,
+            database.collections.len(),
+            format_bytes(total)
+        );
+        for (idx, coll) in database.collections.iter().enumerate() {
+            let branch = if idx + 1 == database.collections.len() {
+                "└──"
+            } else {
+                "├──"
+            };
+            println!(
+                "  {} {:<40} {:>12} docs {:>12} ({} on disk)",
+                branch,
+                coll.name,
+                coll.documents,
+                format_bytes(coll.size),
+                format_bytes(coll.storage_size)
+            );
+        }
+    }
+}
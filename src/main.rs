@@ -1,23 +1,65 @@
-mod config;
-mod keystore;
-mod mongo;
+mod audit;
+mod exit;
+mod history;
+mod job;
+mod list;
+mod presets;
+mod schema;
 mod ui;
+mod uris;
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use audit::AuditEntry;
+use clap::{ArgAction, ArgGroup, Parser, Subcommand};
+use mongodb::bson::{Bson, DateTime, Document, oid::ObjectId};
+use mongodb::options::Hint;
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
-use mongo::{MongoConnection, copy_collection, copy_database};
+use exit::{ConnectionFailed, ExitStatus, InvalidArgs};
+use list::OutputFormat;
+use mongo_copy::checksum::verify_checksums;
+use mongo_copy::config::{CONFIG_PATH_ENV_VAR, Config, Defaults};
+use mongo_copy::dump::{dump_database, restore_dump_collection, scan_dump};
+use mongo_copy::json::parse_document;
+use mongo_copy::keystore::KeyStore;
+#[cfg(feature = "metrics")]
+use mongo_copy::metrics::{MetricsExporter, MetricsTarget};
+use mongo_copy::mongo::{validate_collection_options, validate_projection};
+use mongo_copy::namespace::{
+    case_collisions, check_namespace_length, dated_collection_name, glob_match, is_system_database,
+    parse_namespace,
+};
+use mongo_copy::uri::is_plaintext_remote;
+use mongo_copy::{
+    CollectionSlice, ConflictPolicy, ConnectOptions, CopyError, CopyOptions, CopyPlan, CopyResult,
+    CopyStatus, MongoConnection, OversizedPolicy, ReadConcern, SinceFilter, TtlIndex,
+    WriteStrategy, build_plan, compare_collections, copy_collection, copy_collections,
+    copy_gridfs_bucket, format_bytes, mask_uri, percent_limit, rank_collections,
+    resolve_limit_percent, sample_documents,
+};
+use presets::{PresetCommand, preset_documents};
 use ui::{
-    CopyMode, confirm_operation, get_copy_limit, get_destination_collection,
-    get_destination_database, get_mongodb_uri, select_collections, select_copy_mode,
-    select_databases, select_source_database,
+    Confirmation, CopyMode, Session, UriOrigin, confirm_operation, confirm_or_edit_operation,
+    confirm_plaintext_connection, confirm_system_destination, copy_something_else, get_copy_filter,
+    get_copy_limit, get_copy_projection, get_destination_collection, get_destination_database,
+    get_mongodb_uri, print_checksum_report, print_collection_preview, print_comparison, print_plan,
+    print_sample_documents, select_collections, select_copy_mode, select_databases,
+    select_dump_collections, select_gridfs_buckets, select_source_database,
 };
+use uris::UrisCommand;
 
 #[derive(Parser)]
 #[command(name = "mongo-copy")]
+#[command(group(ArgGroup::new("bulk").args(["ns", "copy", "from_dump", "to_dump"])))]
 #[command(about = "Copy MongoDB databases and collections between instances", long_about = None)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Source MongoDB URI (overrides MONGODB_URI_SOURCE env var)
     #[arg(long)]
     source: Option<String>,
@@ -26,24 +68,489 @@ struct Cli {
     #[arg(long)]
     destination: Option<String>,
 
+    /// Use the URI saved under this name as the source
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["source", "source_env", "from_dump"])]
+    source_name: Option<String>,
+
+    /// Use the URI saved under this name as the destination
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["destination", "dest_env", "to_dump"])]
+    dest_name: Option<String>,
+
     /// Skip environment variables and show saved URIs
     #[arg(long)]
     skip_env: bool,
+
+    /// Read the source URI from this environment variable instead of MONGODB_URI_SOURCE
+    #[arg(long, value_name = "VAR", conflicts_with = "skip_env")]
+    source_env: Option<String>,
+
+    /// Read the destination URI from this environment variable instead of MONGODB_URI_DESTINATION
+    #[arg(long, value_name = "VAR", conflicts_with = "skip_env")]
+    dest_env: Option<String>,
+
+    /// Copy every collection matching a namespace glob, e.g. 'analytics.events_*'
+    #[arg(long, value_name = "PATTERN")]
+    ns: Option<String>,
+
+    /// Copy this namespace, optionally to another one with 'db.coll=destdb.destcoll'; repeatable
+    #[arg(long, value_name = "NS[=NS]", value_parser = parse_copy_spec)]
+    copy: Vec<CopySpec>,
+
+    /// Restore collections from a mongodump output directory instead of a source cluster
+    #[arg(long, value_name = "DIR")]
+    from_dump: Option<PathBuf>,
+
+    /// With --from-dump, build only the dumped index with this name; repeatable
+    #[arg(long = "index", value_name = "NAME", requires = "from_dump")]
+    indexes: Vec<String>,
+
+    /// With --from-dump, don't build the dumped index with this name; repeatable
+    #[arg(long = "skip-index", value_name = "NAME", requires = "from_dump")]
+    skip_indexes: Vec<String>,
+
+    /// Write selected databases to a directory in mongodump layout instead of a destination cluster
+    #[arg(long, value_name = "DIR")]
+    to_dump: Option<PathBuf>,
+
+    /// Destination database for --ns matches, --copy namespaces without a target, --from-dump collections or the --to-dump directory (defaults to each source database)
+    #[arg(long, requires = "bulk")]
+    dest_db: Option<String>,
+
+    /// Prefix the source database name with this to form the default destination database in the prompts
+    #[arg(long, value_name = "PREFIX")]
+    dest_db_prefix: Option<String>,
+
+    /// Suffix the source database name with this to form the default destination database in the prompts, e.g. '_staging'
+    #[arg(long, value_name = "SUFFIX")]
+    dest_db_suffix: Option<String>,
+
+    /// Append today's date to each destination collection name, e.g. events_2024_06 (collection and --ns modes)
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "%Y_%m",
+        value_parser = parse_date_format
+    )]
+    dest_suffix_date: Option<String>,
+
+    /// In database mode, list only databases whose name matches this regex, e.g. '^tenant_'; without a terminal, all matches are selected
+    #[arg(long, value_name = "REGEX", value_parser = parse_db_pattern)]
+    db_pattern: Option<Regex>,
+
+    /// In database mode, copy only the N largest collections by data size
+    #[arg(long, value_name = "N", conflicts_with = "bottom", value_parser = clap::value_parser!(u64).range(1..))]
+    top: Option<u64>,
+
+    /// In database mode, copy only the N smallest collections by data size
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    bottom: Option<u64>,
+
+    /// In database mode, how to treat collections that already exist at the destination
+    #[arg(long, value_enum, value_name = "POLICY")]
+    on_conflict: Option<ConflictPolicy>,
+
+    /// Write each collection in a single destination transaction (replica set or sharded cluster only)
+    #[arg(long)]
+    transactional: bool,
+
+    /// Shard each destination collection on this key before copying, e.g. '{"userId": 1}'
+    #[arg(long, value_name = "JSON", value_parser = parse_shard_key)]
+    shard_key: Option<Document>,
+
+    /// Only copy documents with _id >= this value (inclusive); an ObjectId hex string or a number
+    #[arg(long, value_name = "ID", value_parser = parse_id_bound)]
+    id_min: Option<Bson>,
+
+    /// Only copy documents with _id < this value (exclusive); an ObjectId hex string or a number
+    #[arg(long, value_name = "ID", value_parser = parse_id_bound)]
+    id_max: Option<Bson>,
+
+    /// Apply the projection in this JSON file to the source find, e.g. '{"name": 1, "address.city": 1}'
+    #[arg(long, value_name = "PATH", value_parser = parse_projection_file)]
+    projection_file: Option<Document>,
+
+    /// Copy this percentage of each collection's estimated document count, rounded up, e.g. '10' or '0.5'; replaces the limit prompt
+    #[arg(long, value_name = "PERCENT", value_parser = parse_limit_percent)]
+    limit_percent: Option<f64>,
+
+    /// Apply the filter and projection saved with 'mongo-copy preset add' under this name
+    #[arg(long, value_name = "NAME", conflicts_with = "from_dump")]
+    filter_preset: Option<String>,
+
+    /// Filter of --filter-preset, looked up in the config at startup
+    #[arg(skip)]
+    preset_filter: Option<Document>,
+
+    /// Create destination collections with the `create` options in this JSON file instead of the source's, e.g. '{"capped": true, "size": 1048576}'
+    #[arg(long, value_name = "PATH", value_parser = parse_collection_options_file)]
+    dest_collection_options: Option<Document>,
+
+    /// Make the source find use this index, given as its key pattern ('{"createdAt": 1}') or its name
+    #[arg(long, value_name = "INDEX", value_parser = parse_hint)]
+    hint: Option<Hint>,
+
+    /// Make copied documents expire this many seconds after the date in --ttl-field, via a TTL index on the destination
+    #[arg(long, value_name = "SECONDS", requires = "ttl_field", value_parser = clap::value_parser!(u64).range(1..))]
+    dest_ttl: Option<u64>,
+
+    /// Date field the --dest-ttl expiry counts from
+    #[arg(long, value_name = "FIELD", requires = "dest_ttl")]
+    ttl_field: Option<String>,
+
+    /// Only copy documents whose --time-field is within this long before the run started, e.g. '30m', '24h', '7d'
+    #[arg(long, value_name = "DURATION", requires = "time_field", value_parser = parse_since)]
+    since: Option<DateTime>,
+
+    /// Date field --since compares against
+    #[arg(long, value_name = "FIELD", requires = "since", value_parser = parse_field_path)]
+    time_field: Option<String>,
+
+    /// Read the source through an aggregation $match stage (extended JSON) instead of a find, e.g. '{"status": {"$in": ["active", "trial"]}}'
+    #[arg(long = "match", value_name = "JSON", value_parser = parse_match_stage, conflicts_with_all = ["from_dump", "auto_resume_cursor", "no_cursor_timeout"])]
+    match_stage: Option<Document>,
+
+    /// Leave this field out of every copied document, server-side; repeatable, dotted paths allowed
+    #[arg(long, value_name = "FIELD", value_parser = parse_field_path)]
+    exclude_field: Vec<String>,
+
+    /// Add this top-level field to every copied document, e.g. 'migrated=true' or 'source="legacy"'; repeatable, the value is (extended) JSON
+    #[arg(long, value_name = "KEY=JSON", value_parser = parse_set_field)]
+    set_field: Vec<(String, Bson)>,
+
+    /// Let --set-field replace a field the source document already has
+    #[arg(long, requires = "set_field")]
+    force: bool,
+
+    /// Upsert instead of insert, matching existing documents on these fields rather than _id, e.g. 'email,tenantId'
+    #[arg(long, value_name = "FIELDS", value_delimiter = ',', value_parser = parse_field_path)]
+    upsert_key: Vec<String>,
+
+    /// Only insert documents whose _id is not at the destination yet, checking each batch with one extra destination read
+    #[arg(long, conflicts_with = "upsert_key")]
+    missing_only: bool,
+
+    /// In collection mode, print this many source documents matching the filter before confirming
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..=100))]
+    preview: Option<u64>,
+
+    /// Treat source documents larger than this many serialized bytes as oversized
+    #[arg(long, value_name = "BYTES", value_parser = clap::value_parser!(u64).range(1..))]
+    max_document_bytes: Option<u64>,
+
+    /// What to do with oversized documents
+    #[arg(long, value_enum, default_value_t = OversizedPolicy::Fail, requires = "max_document_bytes")]
+    oversized: OversizedPolicy,
+
+    /// Append the _id and reason of every skipped document to this file as JSON lines
+    #[arg(long, value_name = "PATH", requires = "max_document_bytes")]
+    skipped_report: Option<PathBuf>,
+
+    /// Insert documents even if they fail the destination collection's validator
+    #[arg(long)]
+    bypass_validation: bool,
+
+    /// Insert each batch in order, stopping at the first failed document; 'false' lets the server insert in any order and continue past failures, which can be faster
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = ArgAction::Set)]
+    ordered: bool,
+
+    /// Documents the source server returns per cursor round trip; independent of write batching
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    cursor_batch_size: Option<u32>,
+
+    /// Stop the source server reaping the cursor after 10 idle minutes, for very long copies
+    #[arg(long)]
+    no_cursor_timeout: bool,
+
+    /// Read the source from one point-in-time snapshot for the whole database copy (MongoDB 5.0+ replica set or sharded cluster)
+    #[arg(long, conflicts_with_all = ["from_dump", "to_dump"])]
+    snapshot: bool,
+
+    /// Read the source in _id order and, if the server kills the cursor ("cursor not found"), reopen it after the last _id read
+    #[arg(long, conflicts_with_all = ["snapshot", "from_dump", "to_dump"])]
+    auto_resume_cursor: bool,
+
+    /// Continue a failed database copy, skipping collections it already completed
+    #[arg(long, conflicts_with_all = ["from_dump", "to_dump"])]
+    resume: bool,
+
+    /// Throttle each copy to at most this many documents per second, to spare a live source
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_docs_per_second: Option<u64>,
+
+    /// Flush a write batch before it grows past this many serialized bytes (default 16MiB, or the last value given)
+    #[arg(long, value_name = "BYTES", value_parser = clap::value_parser!(u64).range(1..))]
+    max_batch_bytes: Option<u64>,
+
+    /// Flush a write batch once it holds this many documents (default 1000); with --max-batch-bytes this bounds how much of the source is held in memory
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..=100_000))]
+    max_batch_documents: Option<u64>,
+
+    /// Allow copying into the admin, local or config databases (asks for confirmation)
+    #[arg(long)]
+    allow_system_dest: bool,
+
+    /// Compare document counts, indexes and sizes on both sides after copying
+    #[arg(long)]
+    compare: bool,
+
+    /// Hash every copied document on both sides after copying and report any that differ (slow)
+    #[arg(long)]
+    checksum: bool,
+
+    /// With --checksum, only hash the first N documents of each copy
+    #[arg(long, value_name = "N", requires = "checksum", value_parser = clap::value_parser!(u64).range(1..))]
+    checksum_sample: Option<u64>,
+
+    /// Read concern for source reads, overriding readConcernLevel in the URI; snapshot needs MongoDB 5.0+
+    #[arg(long, value_enum, value_name = "LEVEL")]
+    read_concern: Option<ReadConcern>,
+
+    /// Read the source only from replica set members with these tags, e.g. 'region=us-east' or 'region=us-east,use=analytics'; a secondary unless the URI sets readPreference
+    #[arg(long, value_name = "KEY=VALUE", value_delimiter = ',', value_parser = parse_read_tag)]
+    read_tags: Vec<(String, String)>,
+
+    /// Don't test each connection on startup; connection errors surface during the copy instead
+    #[arg(long, global = true)]
+    skip_connection_test: bool,
+
+    /// Retry failed connections this many times with exponential backoff
+    #[arg(long, value_name = "N", default_value_t = 0, global = true)]
+    connect_retries: u32,
+
+    /// Append a JSON line describing each completed copy operation to this file
+    #[arg(long, value_name = "PATH", global = true)]
+    audit_log: Option<PathBuf>,
+
+    /// Don't record this run's operations in the history kept in the config directory
+    #[arg(long, global = true)]
+    no_history: bool,
+
+    /// Use this config file instead of the default (also set via MONGO_COPY_CONFIG)
+    #[arg(long, value_name = "PATH", global = true)]
+    config: Option<PathBuf>,
+
+    /// Keyring service name for saved URIs, default mongo-copy (also set via MONGO_COPY_KEYRING_SERVICE)
+    #[arg(long, value_name = "NAME", global = true)]
+    keyring_service: Option<String>,
+
+    /// Load environment variables from this file instead of ./.env
+    #[arg(long, value_name = "PATH", global = true)]
+    env_file: Option<PathBuf>,
+
+    /// Connect to remote hosts without TLS without warning or asking first
+    #[arg(long, global = true)]
+    allow_insecure: bool,
+
+    /// Forget the copy mode and batch size remembered from previous runs
+    #[arg(long, global = true)]
+    reset_defaults: bool,
+
+    /// Print the plan without copying anything
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// List collections for selection without their document counts, for databases with very many collections
+    #[arg(long)]
+    no_counts: bool,
+
+    /// How to print the copy plan for --ns and --copy
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    plan_format: OutputFormat,
+
+    /// Increase log verbosity (-v for debug, -vv for trace)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Only log warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Push copy metrics to statsd://host:port or a Prometheus pushgateway at http://host:port
+    #[cfg(feature = "metrics")]
+    #[arg(long, value_name = "URL", global = true, value_parser = parse_metrics_target)]
+    metrics: Option<MetricsTarget>,
+
+    /// Seconds between metrics pushes
+    #[cfg(feature = "metrics")]
+    #[arg(long, value_name = "SECS", default_value_t = 10, global = true, value_parser = clap::value_parser!(u64).range(1..))]
+    metrics_interval: u64,
+}
+
+impl Cli {
+    /// The keyring holding saved URIs, under `--keyring-service` if given
+    fn keystore(&self) -> KeyStore {
+        KeyStore::new(self.keyring_service.as_deref())
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the copy jobs described in a YAML job file
+    Run {
+        /// Path to the job file
+        file: PathBuf,
+    },
+    /// Check a job file against the job file schema without connecting
+    Validate {
+        /// Path to the job file
+        file: PathBuf,
+    },
+    /// List databases and collections with document counts and sizes
+    List(list::ListArgs),
+    /// Show recent copy operations from the history kept in the config directory
+    History(history::HistoryArgs),
+    /// Manage saved URIs
+    Uris {
+        #[command(subcommand)]
+        command: UrisCommand,
+    },
+    /// Manage saved filter and projection presets
+    Preset {
+        #[command(subcommand)]
+        command: PresetCommand,
+    },
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize tracing subscriber
+fn main() {
+    let mut cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        // --help and --version are not errors
+        Err(e) if !e.use_stderr() => e.exit(),
+        Err(e) => {
+            let _ = e.print();
+            std::process::exit(ExitStatus::InvalidArgs.code());
+        }
+    };
+
+    // Initialize tracing subscriber, an explicit RUST_LOG wins over the flags
+    let default_level = match (cli.quiet, cli.verbose) {
+        (true, _) => "warn",
+        (false, 0) => "info",
+        (false, 1) => "debug",
+        (false, _) => "trace",
+    };
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(default_level)),
         )
         .init();
 
-    let cli = Cli::parse();
-
     info!("MongoDB Copy");
+
+    let status = match start_runtime(&cli) {
+        Ok(runtime) => runtime.block_on(run_to_exit(&mut cli)),
+        Err(e) => report_error(&e),
+    };
+    debug!("Exiting with status {:?} ({})", status, status.code());
+    std::process::exit(status.code());
+}
+
+/// Apply the env file and environment overrides, then start the runtime.
+/// The environment must not change once the runtime's worker threads exist
+fn start_runtime(cli: &Cli) -> Result<tokio::runtime::Runtime> {
+    load_env_file(cli.env_file.as_deref())?;
+
+    if let Some(path) = &cli.config {
+        // Every Config::load/save resolves its path through this variable
+        debug!("Using config file: {:?}", path);
+        std::env::set_var(CONFIG_PATH_ENV_VAR, path);
+    }
+
+    tokio::runtime::Runtime::new().context("Failed to start the async runtime")
+}
+
+async fn run_to_exit(cli: &mut Cli) -> ExitStatus {
+    #[cfg(feature = "metrics")]
+    let exporter = cli
+        .metrics
+        .clone()
+        .map(|target| MetricsExporter::start(target, Duration::from_secs(cli.metrics_interval)));
+
+    let status = run(cli).await.unwrap_or_else(|e| report_error(&e));
+
+    #[cfg(feature = "metrics")]
+    if let Some(exporter) = exporter {
+        exporter.finish().await;
+    }
+    status
+}
+
+fn report_error(error: &anyhow::Error) -> ExitStatus {
+    eprintln!("Error: {:?}", error);
+    ExitStatus::for_error(error)
+}
+
+async fn run(cli: &mut Cli) -> Result<ExitStatus> {
+    if let (Some(start), Some(field)) = (cli.since, &cli.time_field) {
+        info!(
+            "Only copying documents with '{}' on or after {}",
+            field,
+            start
+                .try_to_rfc3339_string()
+                .unwrap_or_else(|_| start.to_string())
+        );
+    }
+
+    let mut config = Config::load()?;
+    if cli.reset_defaults {
+        info!("Clearing remembered defaults");
+        config.set_defaults(Defaults::default())?;
+    }
+    let explicit_batch_bytes = cli.max_batch_bytes;
+    if cli.max_batch_bytes.is_none() {
+        cli.max_batch_bytes = config.defaults.max_batch_bytes;
+    }
+    if let Some(name) = &cli.filter_preset {
+        let preset = config.get_preset(name).with_context(|| {
+            format!(
+                "No preset named '{}'; save one with 'mongo-copy preset add'",
+                name
+            )
+        })?;
+        let (filter, projection) = preset_documents(preset)?;
+        if projection.is_some() && cli.projection_file.is_some() {
+            anyhow::bail!(
+                "Preset '{}' has a projection, so it can't be combined with --projection-file",
+                name
+            );
+        }
+        info!("Using preset '{}'", name);
+        cli.preset_filter = filter;
+        cli.projection_file = cli.projection_file.take().or(projection);
+    }
+    let cli = &*cli;
+
+    match &cli.command {
+        Some(Command::Run { file }) => {
+            debug!("Running job file: {:?}", file);
+            job::run(file, cli).await?;
+            return Ok(ExitStatus::Success);
+        }
+        Some(Command::Validate { file }) => {
+            job::validate(file)?;
+            return Ok(ExitStatus::Success);
+        }
+        Some(Command::List(args)) => {
+            list::run(args, cli).await?;
+            return Ok(ExitStatus::Success);
+        }
+        Some(Command::History(args)) => {
+            history::run(args)?;
+            return Ok(ExitStatus::Success);
+        }
+        Some(Command::Uris { command }) => {
+            uris::run(command, cli).await?;
+            return Ok(ExitStatus::Success);
+        }
+        Some(Command::Preset { command }) => {
+            presets::run(command)?;
+            return Ok(ExitStatus::Success);
+        }
+        None => {}
+    }
+
     debug!(
         "Parsed CLI arguments: source={:?}, destination={:?}, skip_env={}",
         cli.source.is_some(),
@@ -51,29 +558,86 @@ async fn main() -> Result<()> {
         cli.skip_env
     );
 
+    let keystore = cli.keystore();
+
     // Get source URI
-    let source_uri = if let Some(uri) = cli.source {
+    let (source_uri, source_origin) = if let Some(dir) = &cli.from_dump {
+        debug!("Restoring from dump directory: {:?}", dir);
+        (dir.display().to_string(), UriOrigin::Dump)
+    } else if let Some(uri) = &cli.source {
         debug!("Using source URI from CLI argument");
-        uri
+        (uri.clone(), UriOrigin::Argument)
+    } else if let Some(name) = &cli.source_name {
+        (saved_uri(&keystore, name)?, UriOrigin::Saved(name.clone()))
     } else {
         get_mongodb_uri(
-            "MONGODB_URI_SOURCE",
+            &keystore,
+            &uri_env_var(cli.source_env.as_deref(), "MONGODB_URI_SOURCE")?,
             "Select or enter source MongoDB URI:",
             cli.skip_env,
         )?
     };
 
     // Get destination URI
-    let dest_uri = if let Some(uri) = cli.destination {
+    let (dest_uri, dest_origin) = if let Some(dir) = &cli.to_dump {
+        debug!("Dumping to directory: {:?}", dir);
+        (dir.display().to_string(), UriOrigin::Dump)
+    } else if let Some(uri) = &cli.destination {
         debug!("Using destination URI from CLI argument");
-        uri
+        (uri.clone(), UriOrigin::Argument)
+    } else if let Some(name) = &cli.dest_name {
+        (saved_uri(&keystore, name)?, UriOrigin::Saved(name.clone()))
     } else {
         get_mongodb_uri(
-            "MONGODB_URI_DESTINATION",
+            &keystore,
+            &uri_env_var(cli.dest_env.as_deref(), "MONGODB_URI_DESTINATION")?,
             "Select or enter destination MongoDB URI:",
             cli.skip_env,
         )?
     };
+    let mut session = Session::new(source_origin, dest_origin);
+    if cli.bypass_validation {
+        session
+            .warnings
+            .push("Document validation is bypassed on the destination".to_string());
+    }
+    if cli.no_cursor_timeout {
+        session.warnings.push(
+            "Source cursors never time out and hold server resources until each copy ends"
+                .to_string(),
+        );
+    }
+
+    if let Some(dir) = &cli.from_dump {
+        check_plaintext(cli, "destination", &dest_uri, true)?;
+        info!("Connecting to destination: {}", mask_uri(&dest_uri));
+        let dest = MongoConnection::connect_with_retries(
+            &dest_uri,
+            cli.connect_retries,
+            connect_options(cli, false),
+        )
+        .await
+        .map_err(|e| e.into_inner().context(ConnectionFailed("destination")))?;
+        let skipped = handle_dump_restore(dir, &dest, cli, &session).await?;
+        return Ok(finish(skipped));
+    }
+
+    if let Some(dir) = &cli.to_dump {
+        check_plaintext(cli, "source", &source_uri, true)?;
+        info!("Connecting to source: {}", mask_uri(&source_uri));
+        let source = MongoConnection::connect_with_retries(
+            &source_uri,
+            cli.connect_retries,
+            connect_options(cli, true),
+        )
+        .await
+        .map_err(|e| e.into_inner().context(ConnectionFailed("source")))?;
+        let skipped = handle_database_dump(&source, dir, cli, &session).await?;
+        return Ok(finish(skipped));
+    }
+
+    check_plaintext(cli, "source", &source_uri, true)?;
+    check_plaintext(cli, "destination", &dest_uri, true)?;
 
     info!("Connecting to MongoDB instances...");
     info!("Source:      {}", mask_uri(&source_uri));
@@ -85,160 +649,1372 @@ async fn main() -> Result<()> {
     );
 
     // Connect to both instances
-    match MongoConnection::new(&source_uri).await {
+    match MongoConnection::connect_with_retries(
+        &source_uri,
+        cli.connect_retries,
+        connect_options(cli, true),
+    )
+    .await
+    {
         Ok(source) => {
             debug!("Successfully connected to source MongoDB");
-            match MongoConnection::new(&dest_uri).await {
+            match MongoConnection::connect_with_retries(
+                &dest_uri,
+                cli.connect_retries,
+                connect_options(cli, false),
+            )
+            .await
+            {
                 Ok(dest) => {
                     info!("Connected successfully");
                     debug!("Both MongoDB connections established");
 
-                    // Select copy mode
-                    let mode = select_copy_mode()?;
-                    debug!(
-                        "Selected copy mode: {:?}",
-                        match mode {
-                            CopyMode::Databases => "Databases",
-                            CopyMode::Collections => "Collections",
-                        }
-                    );
+                    if let Some(pattern) = &cli.ns {
+                        let skipped =
+                            handle_namespace_copy(&source, &dest, pattern, cli, &mut session)
+                                .await?;
+                        return Ok(finish(skipped));
+                    }
+                    if !cli.copy.is_empty() {
+                        let skipped =
+                            handle_explicit_copy(&source, &dest, &cli.copy, cli, &mut session)
+                                .await?;
+                        return Ok(finish(skipped));
+                    }
 
-                    match mode {
-                        CopyMode::Databases => {
-                            handle_database_copy(&source, &dest).await?;
-                        }
-                        CopyMode::Collections => {
-                            handle_collection_copy(&source, &dest).await?;
+                    let mut results = Vec::new();
+                    loop {
+                        // Select copy mode
+                        let default_mode = config
+                            .defaults
+                            .copy_mode
+                            .as_deref()
+                            .and_then(CopyMode::from_key);
+                        let mode = select_copy_mode(default_mode)?;
+                        remember_defaults(&mut config, mode, explicit_batch_bytes);
+                        debug!(
+                            "Selected copy mode: {:?}",
+                            match mode {
+                                CopyMode::Databases => "Databases",
+                                CopyMode::Collections => "Collections",
+                                CopyMode::GridFs => "GridFS",
+                            }
+                        );
+
+                        results.extend(match mode {
+                            CopyMode::Databases => {
+                                handle_database_copy(&source, &dest, cli, &mut session).await?
+                            }
+                            CopyMode::Collections => {
+                                handle_collection_copy(&source, &dest, cli, &mut session).await?
+                            }
+                            CopyMode::GridFs => {
+                                handle_gridfs_copy(&source, &dest, cli, &session).await?
+                            }
+                        });
+
+                        if !copy_something_else()? {
+                            break;
                         }
                     }
 
-                    info!("All operations completed successfully!");
-                    Ok(())
+                    print_results(&results);
+                    let skipped = results
+                        .iter()
+                        .filter(|result| result.status == CopyStatus::Skipped)
+                        .count();
+                    Ok(finish(skipped))
                 }
                 Err(e) => {
                     error!("Failed to connect to destination MongoDB: {}", e);
-                    Err(e)
+                    Err(e.into_inner().context(ConnectionFailed("destination")))
                 }
             }
         }
         Err(e) => {
             error!("Failed to connect to source MongoDB: {}", e);
-            Err(e)
+            Err(e.into_inner().context(ConnectionFailed("source")))
+        }
+    }
+}
+
+/// Remember this run's choices as the next run's defaults; failing to save
+/// them is not worth stopping the copy for
+fn remember_defaults(config: &mut Config, mode: CopyMode, max_batch_bytes: Option<u64>) {
+    let defaults = Defaults {
+        copy_mode: Some(mode.key().to_string()),
+        max_batch_bytes: max_batch_bytes.or(config.defaults.max_batch_bytes),
+    };
+    if let Err(e) = config.set_defaults(defaults) {
+        warn!("Failed to save defaults: {:#}", e);
+    }
+}
+
+/// Warn about selected names that would land on top of each other at a
+/// case-insensitive destination
+fn warn_case_collisions<'a>(kind: &str, names: impl IntoIterator<Item = &'a str>) {
+    for group in case_collisions(names) {
+        warn!(
+            "{} {} would collide at a case-insensitive destination",
+            kind,
+            group.join(", ")
+        );
+    }
+}
+
+/// One line per operation of the run, once more than one was selected
+fn print_results(results: &[CopyResult]) {
+    if results.len() < 2 {
+        return;
+    }
+    info!("Summary:");
+    for result in results {
+        match result.status {
+            CopyStatus::Copied => info!(
+                "  copied   {}: {} documents ({}) in {:.1?}",
+                result.namespace,
+                result.stats.documents,
+                format_bytes(result.stats.bytes),
+                result.duration
+            ),
+            CopyStatus::Skipped => info!("  skipped  {}", result.namespace),
+        }
+    }
+}
+
+/// Map the number of skipped operations to the final exit status
+fn finish(skipped: usize) -> ExitStatus {
+    if skipped == 0 {
+        info!("All operations completed successfully!");
+        ExitStatus::Success
+    } else {
+        warn!("Completed with {} operation(s) skipped", skipped);
+        ExitStatus::Partial
+    }
+}
+
+/// Connection settings from the CLI flags; only the source is given a read
+/// concern and read tags
+pub(crate) fn connect_options(cli: &Cli, source: bool) -> ConnectOptions {
+    let options = ConnectOptions::default().with_skip_test(cli.skip_connection_test);
+    if source {
+        options
+            .with_read_concern(cli.read_concern)
+            .with_read_tags(cli.read_tags.clone())
+    } else {
+        options
+    }
+}
+
+/// Unwrap a library error for the CLI, logging what the user can do about
+/// the failures they can fix
+pub(crate) fn copy_failure(error: CopyError) -> anyhow::Error {
+    match &error {
+        CopyError::DuplicateKey(_) => info!(
+            "A document with the same _id or unique key is already at the destination; --missing-only skips those, --upsert-key overwrites them"
+        ),
+        CopyError::NamespaceNotFound(_) => {
+            info!("The source collection may have been dropped or renamed during the copy")
+        }
+        _ => {}
+    }
+    error.into_inner()
+}
+
+/// Build the copy options for one collection from the CLI flags
+fn copy_options(
+    cli: &Cli,
+    source_db: &str,
+    source_coll: &str,
+    dest_db: &str,
+    dest_coll: &str,
+) -> CopyOptions {
+    let options = CopyOptions::new(source_db, source_coll, dest_db, dest_coll)
+        .with_transactional(cli.transactional)
+        .with_shard_key(cli.shard_key.clone())
+        .with_id_range(cli.id_min.clone(), cli.id_max.clone())
+        .with_since(
+            cli.since
+                .zip(cli.time_field.clone())
+                .map(|(start, field)| SinceFilter { field, start }),
+        )
+        .with_filter(cli.preset_filter.clone())
+        .with_projection(cli.projection_file.clone())
+        .with_dest_collection_options(cli.dest_collection_options.clone())
+        .with_exclude_fields(cli.exclude_field.clone())
+        .with_set_fields(cli.set_field.iter().cloned().collect(), cli.force)
+        .with_match_stage(cli.match_stage.clone())
+        .with_hint(cli.hint.clone())
+        .with_ttl(
+            cli.dest_ttl
+                .zip(cli.ttl_field.clone())
+                .map(|(secs, field)| TtlIndex {
+                    field,
+                    expire_after: Duration::from_secs(secs),
+                }),
+        )
+        .with_max_document_bytes(cli.max_document_bytes, cli.oversized)
+        .with_skipped_report(cli.skipped_report.clone())
+        .with_bypass_validation(cli.bypass_validation)
+        .with_unordered(!cli.ordered)
+        .with_limit_percent(cli.limit_percent)
+        .with_max_docs_per_second(cli.max_docs_per_second)
+        .with_max_batch_bytes(cli.max_batch_bytes)
+        .with_max_batch_documents(cli.max_batch_documents.map(|n| n as usize))
+        .with_cursor_batch_size(cli.cursor_batch_size)
+        .with_no_cursor_timeout(cli.no_cursor_timeout)
+        .with_auto_resume_cursor(cli.auto_resume_cursor)
+        .with_snapshot(cli.snapshot)
+        .with_indexes(cli.indexes.clone(), cli.skip_indexes.clone())
+        .with_upsert_key(cli.upsert_key.clone());
+    if cli.missing_only {
+        options.with_write_strategy(WriteStrategy::MissingOnly)
+    } else if cli.upsert_key.is_empty() {
+        options
+    } else {
+        options.with_write_strategy(WriteStrategy::Upsert)
+    }
+}
+
+/// The --top or --bottom slice of collections to copy in database mode
+fn collection_slice(cli: &Cli) -> Option<CollectionSlice> {
+    match (cli.top, cli.bottom) {
+        (Some(n), _) => Some(CollectionSlice::Largest(n as usize)),
+        (None, Some(n)) => Some(CollectionSlice::Smallest(n as usize)),
+        (None, None) => None,
+    }
+}
+
+/// The destination database offered by default for `source_db`, with
+/// --dest-db-prefix and --dest-db-suffix applied
+fn default_dest_database(cli: &Cli, source_db: &str) -> String {
+    format!(
+        "{}{}{}",
+        cli.dest_db_prefix.as_deref().unwrap_or_default(),
+        source_db,
+        cli.dest_db_suffix.as_deref().unwrap_or_default()
+    )
+}
+
+/// The destination collection name for `collection`, dated when
+/// --dest-suffix-date is set
+fn dest_collection_name(cli: &Cli, collection: &str) -> Result<String> {
+    match &cli.dest_suffix_date {
+        Some(format) => dated_collection_name(collection, format, DateTime::now()),
+        None => Ok(collection.to_string()),
+    }
+}
+
+/// A `--copy` namespace and its optional explicit target
+#[derive(Debug, Clone)]
+struct CopySpec {
+    source: (String, String),
+    dest: Option<(String, String)>,
+}
+
+fn parse_copy_spec(input: &str) -> Result<CopySpec, String> {
+    let (source, dest) = match input.split_once('=') {
+        Some((source, dest)) => (source, Some(dest)),
+        None => (input, None),
+    };
+    Ok(CopySpec {
+        source: parse_namespace(source.trim()).map_err(|e| e.to_string())?,
+        dest: dest
+            .map(|dest| parse_namespace(dest.trim()).map_err(|e| e.to_string()))
+            .transpose()?,
+    })
+}
+
+fn parse_date_format(input: &str) -> Result<String, String> {
+    dated_collection_name("", input, DateTime::now()).map_err(|e| e.to_string())?;
+    Ok(input.to_string())
+}
+
+fn parse_id_bound(input: &str) -> Result<Bson, String> {
+    if let Ok(oid) = ObjectId::parse_str(input) {
+        return Ok(Bson::ObjectId(oid));
+    }
+    if let Ok(n) = input.parse::<i64>() {
+        return Ok(Bson::Int64(n));
+    }
+    if let Ok(n) = input.parse::<f64>() {
+        return Ok(Bson::Double(n));
+    }
+    Err(format!(
+        "'{}' is neither a 24-character ObjectId hex string nor a number",
+        input
+    ))
+}
+
+fn parse_projection_file(path: &str) -> Result<Document, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read projection file '{}': {}", path, e))?;
+    let projection = parse_document(&content).map_err(|e| format!("{:#}", e))?;
+    validate_projection(&projection).map_err(|e| e.to_string())?;
+    Ok(projection)
+}
+
+fn parse_collection_options_file(path: &str) -> Result<Document, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read collection options file '{}': {}", path, e))?;
+    let options = parse_document(&content).map_err(|e| format!("{:#}", e))?;
+    validate_collection_options(&options).map_err(|e| e.to_string())?;
+    Ok(options)
+}
+
+#[cfg(feature = "metrics")]
+fn parse_metrics_target(input: &str) -> Result<MetricsTarget, String> {
+    MetricsTarget::parse(input).map_err(|e| e.to_string())
+}
+
+fn parse_hint(input: &str) -> Result<Hint, String> {
+    if input.trim_start().starts_with('{') {
+        let keys = parse_document(input).map_err(|e| format!("{:#}", e))?;
+        if keys.is_empty() {
+            return Err("hint must name at least one field".to_string());
+        }
+        Ok(Hint::Keys(keys))
+    } else if input.trim().is_empty() {
+        Err("hint must be a key pattern or an index name".to_string())
+    } else {
+        Ok(Hint::Name(input.to_string()))
+    }
+}
+
+fn parse_db_pattern(input: &str) -> Result<Regex, String> {
+    Regex::new(input).map_err(|e| e.to_string())
+}
+
+fn parse_set_field(input: &str) -> Result<(String, Bson), String> {
+    let (key, value) = input
+        .split_once('=')
+        .ok_or_else(|| format!("'{}' must be KEY=JSON, e.g. 'migrated=true'", input))?;
+    if key.is_empty() || key.starts_with('$') || key.contains('.') {
+        return Err(format!("'{}' is not a valid top-level field name", key));
+    }
+    if key == "_id" {
+        return Err("_id can't be set on copied documents".to_string());
+    }
+    let value: serde_json::Value = serde_json::from_str(value).map_err(|_| {
+        format!(
+            "value of '{}' is not valid JSON; quote strings, e.g. '{}=\"{}\"'",
+            key, key, value
+        )
+    })?;
+    let value = Bson::try_from(value).map_err(|e| format!("invalid extended JSON: {}", e))?;
+    Ok((key.to_string(), value))
+}
+
+fn parse_match_stage(input: &str) -> Result<Document, String> {
+    let stage = parse_document(input).map_err(|e| format!("{:#}", e))?;
+    if let Some(key) = stage.keys().find(|key| key.starts_with("$match")) {
+        return Err(format!(
+            "pass the contents of the stage, not '{}', e.g. '{{\"status\": \"active\"}}'",
+            key
+        ));
+    }
+    Ok(stage)
+}
+
+fn parse_limit_percent(input: &str) -> Result<f64, String> {
+    match input.trim_end_matches('%').parse::<f64>() {
+        Ok(percent) if percent > 0.0 && percent <= 100.0 => Ok(percent),
+        _ => Err(format!(
+            "'{}' must be a percentage greater than 0 and at most 100",
+            input
+        )),
+    }
+}
+
+fn parse_read_tag(input: &str) -> Result<(String, String), String> {
+    match input.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() && !value.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!(
+            "'{}' must be KEY=VALUE, e.g. 'region=us-east'",
+            input
+        )),
+    }
+}
+
+fn parse_field_path(input: &str) -> Result<String, String> {
+    if input.is_empty() || input.starts_with('$') || input.split('.').any(str::is_empty) {
+        return Err(format!("'{}' is not a valid field path", input));
+    }
+    Ok(input.to_string())
+}
+
+/// Parse a duration such as `30m`, `24h` or `7d` and return the date that
+/// long before now
+fn parse_since(input: &str) -> Result<DateTime, String> {
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (amount, unit) = input.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("'{}' must be a number followed by s, m, h, d or w", input))?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("'{}' must end in s, m, h, d or w", input)),
+    };
+    if amount == 0 {
+        return Err("duration must be greater than zero".to_string());
+    }
+    let millis = amount
+        .checked_mul(unit_secs * 1000)
+        .and_then(|millis| i64::try_from(millis).ok())
+        .ok_or_else(|| format!("'{}' is too long", input))?;
+    Ok(DateTime::from_millis(
+        DateTime::now().timestamp_millis().saturating_sub(millis),
+    ))
+}
+
+fn parse_shard_key(input: &str) -> Result<Document, String> {
+    let key = parse_document(input).map_err(|e| format!("{:#}", e))?;
+    if key.is_empty() {
+        return Err("shard key must name at least one field".to_string());
+    }
+    for (field, value) in &key {
+        let valid = match value {
+            Bson::String(s) => s == "hashed",
+            other => other.as_i32() == Some(1) || other.as_i64() == Some(1),
+        };
+        if !valid {
+            return Err(format!(
+                "shard key field '{}' must be 1 or \"hashed\"",
+                field
+            ));
+        }
+    }
+    Ok(key)
+}
+
+/// Look up a URI saved under `name`, which must exist
+fn saved_uri(keystore: &KeyStore, name: &str) -> Result<String> {
+    debug!("Using saved URI: {}", name);
+    keystore.get_uri(name)?.ok_or_else(|| {
+        InvalidArgs(format!(
+            "No saved URI named '{}'; see the names offered by the interactive prompt, or save one with 'mongo-copy uris add'",
+            name
+        ))
+        .into()
+    })
+}
+
+/// Pick the env var to read a URI from; an explicitly named one must be set
+fn uri_env_var(explicit: Option<&str>, default: &str) -> Result<String> {
+    match explicit {
+        Some(var) if std::env::var_os(var).is_none() => {
+            Err(InvalidArgs(format!("Environment variable {} is not set", var)).into())
+        }
+        Some(var) => Ok(var.to_string()),
+        None => Ok(default.to_string()),
+    }
+}
+
+/// Load variables from a .env file without overriding ones already set
+fn load_env_file(path: Option<&Path>) -> Result<()> {
+    let (path, explicit) = match path {
+        Some(p) => (p.to_path_buf(), true),
+        None => (PathBuf::from(".env"), false),
+    };
+
+    if !path.exists() {
+        if explicit {
+            return Err(InvalidArgs(format!("Env file {:?} does not exist", path)).into());
         }
+        debug!("No .env file found");
+        return Ok(());
     }
+
+    let entries = dotenvy::from_path_iter(&path)
+        .with_context(|| format!("Failed to read env file {:?}", path))?;
+
+    let mut loaded = Vec::new();
+    for entry in entries {
+        let (key, value) = entry.with_context(|| format!("Failed to parse env file {:?}", path))?;
+        if std::env::var_os(&key).is_some() {
+            debug!("Keeping existing value for {}", key);
+            continue;
+        }
+        std::env::set_var(&key, value);
+        loaded.push(key);
+    }
+
+    // Only ever log names, values may contain credentials
+    info!(
+        "Loaded {} variable(s) from {:?}: {}",
+        loaded.len(),
+        path,
+        loaded.join(", ")
+    );
+    Ok(())
 }
 
-async fn handle_database_copy(source: &MongoConnection, dest: &MongoConnection) -> Result<()> {
-    let databases = select_databases(source).await?;
+/// Where database copies record completed collections for `--resume`
+fn resume_dir() -> Result<PathBuf> {
+    let config_path = Config::config_path()?;
+    let dir = config_path
+        .parent()
+        .context("Config path has no parent directory")?;
+    Ok(dir.join("resume"))
+}
+
+async fn handle_database_copy(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    cli: &Cli,
+    session: &mut Session,
+) -> Result<Vec<CopyResult>> {
+    let databases = select_databases(source, cli.db_pattern.as_ref()).await?;
     debug!("Selected {} database(s) for copying", databases.len());
+    warn_case_collisions("Databases", databases.iter().map(String::as_str));
+    let mut results = Vec::new();
+
+    'databases: for source_db in databases {
+        let mut collections = source.list_collections(&source_db).await?;
+        if let Some(slice) = collection_slice(cli) {
+            collections = rank_collections(source, &source_db, &collections, slice).await?;
+        }
+        // A failed count is shown, not fatal: the copy itself decides whether
+        // that collection can be copied
+        let counts: Vec<_> = collections
+            .iter()
+            .cloned()
+            .zip(source.get_display_counts(&source_db, &collections).await)
+            .collect();
+        print_collection_preview(&source_db, &counts);
+        let estimates: Vec<u64> = counts
+            .iter()
+            .filter_map(|(_, count)| count.as_ref().ok().copied().flatten())
+            .collect();
+        let failed = counts.iter().filter(|(_, count)| count.is_err()).count();
+        let total: u64 = estimates.iter().sum();
+        let mut documents = match cli.limit_percent {
+            Some(percent) => {
+                let limited: u64 = estimates
+                    .iter()
+                    .map(|count| percent_limit(*count, percent))
+                    .sum();
+                format!(
+                    "~{} of ~{} documents, {}% of each collection",
+                    limited, total, percent
+                )
+            }
+            None => format!("~{} documents", total),
+        };
+        if failed > 0 {
+            documents.push_str(&format!(", {} count(s) unavailable", failed));
+        }
+
+        let mut dest_db = default_dest_database(cli, &source_db);
+        loop {
+            dest_db = get_destination_database(&dest_db, cli.allow_system_dest)?;
+            debug!("Database copy: '{}' -> '{}'", source_db, dest_db);
+
+            let mut operation = format!(
+                "Copy database '{}' to '{}' ({} collections, {})",
+                source_db,
+                dest_db,
+                counts.len(),
+                documents
+            );
+            if let Some(policy) = cli.on_conflict {
+                operation.push_str(&format!(", {}", policy));
+            }
+            if cli.dry_run {
+                break;
+            }
+
+            match confirm_or_edit_operation(session, &source.uri, &dest.uri, &operation)? {
+                Confirmation::Proceed => break,
+                Confirmation::Edit => continue,
+                Confirmation::Abort => {
+                    warn!(
+                        "Skipped database '{}' - user declined confirmation",
+                        source_db
+                    );
+                    info!("Skipped database '{}'", source_db);
+                    results.push(CopyResult::skipped(source_db));
+                    continue 'databases;
+                }
+            }
+        }
+
+        if cli.dry_run {
+            let planned = collections
+                .iter()
+                .map(|coll| copy_options(cli, &source_db, coll, &dest_db, coll))
+                .collect();
+            print_copy_plan(source, dest, planned, cli).await?;
+            info!("Dry run: no documents were copied");
+            continue;
+        }
+
+        if !source.database_exists(&source_db).await? {
+            anyhow::bail!("Source database '{}' no longer exists", source_db);
+        }
+
+        info!("Starting copy operation for database '{}'", source_db);
+        let template = copy_options(cli, &source_db, "", &dest_db, "")
+            .with_stop(Some(session.stop.clone()))
+            .with_progress_dir(Some(resume_dir()?))
+            .with_resume(cli.resume);
+        let entry = AuditEntry::start("database", &source.uri, &dest.uri, &source_db, &dest_db);
+        let stop_key = session.listen_for_stop();
+        let started = Instant::now();
+        let result = copy_collections(
+            source,
+            dest,
+            &source_db,
+            &dest_db,
+            &collections,
+            &template,
+            cli.on_conflict,
+        )
+        .await
+        .map_err(copy_failure);
+        drop(stop_key);
+        audit::record(cli, &entry.finish(&result))?;
+        match result {
+            Ok(stats) => {
+                let sampled = cli
+                    .limit_percent
+                    .map(|percent| format!(", {}% of each collection", percent))
+                    .unwrap_or_default();
+                info!(
+                    "Database '{}' copied successfully ({} documents, {}{})",
+                    source_db,
+                    stats.documents,
+                    format_bytes(stats.bytes),
+                    sampled
+                );
+                results.push(CopyResult::copied(&source_db, stats, started.elapsed()));
+                for coll in &collections {
+                    session.record_copied(&source_db, coll);
+                    let options = copy_options(cli, &source_db, coll, &dest_db, coll);
+                    verify_copy(source, dest, cli, &options).await?;
+                }
+            }
+            Err(e) => {
+                error!("Failed to copy database '{}': {:#}", source_db, e);
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+async fn handle_database_dump(
+    source: &MongoConnection,
+    dir: &Path,
+    cli: &Cli,
+    session: &Session,
+) -> Result<usize> {
+    let databases = select_databases(source, cli.db_pattern.as_ref()).await?;
+    debug!("Selected {} database(s) for dumping", databases.len());
+    warn_case_collisions("Databases", databases.iter().map(String::as_str));
+    let dest_label = dir.display().to_string();
+    let mut skipped = 0;
 
     for source_db in databases {
-        let dest_db = get_destination_database(&source_db)?;
-        debug!("Database copy: '{}' -> '{}'", source_db, dest_db);
+        let dest_db = cli.dest_db.clone().unwrap_or_else(|| source_db.clone());
+        let operation = format!("Dump database '{}' to {:?}", source_db, dir.join(&dest_db));
 
-        let operation = format!("Copy database '{}' to '{}'", source_db, dest_db);
+        if cli.dry_run {
+            info!("Dry run: {}", operation);
+            continue;
+        }
 
-        if !confirm_operation(&source.uri, &dest.uri, &operation)? {
+        if !confirm_operation(session, &source.uri, &dest_label, &operation)? {
             warn!(
                 "Skipped database '{}' - user declined confirmation",
                 source_db
             );
-            info!("Skipped database '{}'", source_db);
+            skipped += 1;
             continue;
         }
 
-        info!("Starting copy operation for database '{}'", source_db);
-        match copy_database(source, dest, &source_db, &dest_db).await {
-            Ok(_) => {
-                info!("Database '{}' copied successfully", source_db);
+        if !source.database_exists(&source_db).await? {
+            anyhow::bail!("Source database '{}' no longer exists", source_db);
+        }
+
+        let template = copy_options(cli, &source_db, "", &dest_db, "");
+        let entry = AuditEntry::start("to-dump", &source.uri, &dest_label, &source_db, &dest_db);
+        let result = dump_database(source, &source_db, &dest_db, dir, &template).await;
+        audit::record(cli, &entry.finish(&result))?;
+        match result {
+            Ok(stats) => {
+                info!(
+                    "Database '{}' dumped successfully ({} documents, {})",
+                    source_db,
+                    stats.documents,
+                    format_bytes(stats.bytes)
+                );
             }
             Err(e) => {
-                error!("Failed to copy database '{}': {}", source_db, e);
+                error!("Failed to dump database '{}': {}", source_db, e);
                 return Err(e);
             }
         }
     }
 
-    Ok(())
+    Ok(skipped)
 }
 
-async fn handle_collection_copy(source: &MongoConnection, dest: &MongoConnection) -> Result<()> {
+async fn handle_collection_copy(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    cli: &Cli,
+    session: &mut Session,
+) -> Result<Vec<CopyResult>> {
     let source_db = select_source_database(source).await?;
     debug!("Selected source database: '{}'", source_db);
 
-    let collections = select_collections(source, &source_db).await?;
+    let collections = select_collections(source, &source_db, session, !cli.no_counts).await?;
     debug!("Selected {} collection(s) for copying", collections.len());
+    warn_case_collisions("Collections", collections.iter().map(String::as_str));
 
     // Ask for destination database once for all collections
-    let dest_db = get_destination_database(&source_db)?;
+    let dest_db = get_destination_database(
+        &default_dest_database(cli, &source_db),
+        cli.allow_system_dest,
+    )?;
     debug!("Destination database: '{}'", dest_db);
+    let mut results = Vec::new();
+    let mut dry_run_plan = Vec::new();
 
-    for source_coll in &collections {
-        let dest_coll = get_destination_collection(source_coll)?;
-        debug!(
-            "Collection copy: '{}.{}' -> '{}.{}'",
-            source_db, source_coll, dest_db, dest_coll
-        );
+    'collections: for source_coll in &collections {
+        let mut dest_coll = dest_collection_name(cli, source_coll)?;
+        let (limit, filter, projection) = loop {
+            dest_coll = loop {
+                let name = get_destination_collection(&dest_db, &dest_coll)?;
+                if !dest.is_view(&dest_db, &name).await? {
+                    break name;
+                }
+                warn!(
+                    "Destination '{}.{}' is a view, not a collection; choose a different name",
+                    dest_db, name
+                );
+            };
+            debug!(
+                "Collection copy: '{}.{}' -> '{}.{}'",
+                source_db, source_coll, dest_db, dest_coll
+            );
 
-        let limit = get_copy_limit(source, &source_db, source_coll).await?;
-        debug!("Copy limit for '{}': {:?}", source_coll, limit);
+            let limit = match cli.limit_percent {
+                Some(_) => {
+                    let options = copy_options(cli, &source_db, source_coll, &dest_db, &dest_coll);
+                    resolve_limit_percent(source, &options).await?.limit
+                }
+                None => get_copy_limit(source, &source_db, source_coll).await?,
+            };
+            debug!("Copy limit for '{}': {:?}", source_coll, limit);
 
-        let operation = if let Some(limit_val) = limit {
-            format!(
-                "Copy {} documents from '{}.{}' to '{}.{}'",
-                limit_val, source_db, source_coll, dest_db, dest_coll
-            )
-        } else {
-            format!(
-                "Copy all documents from '{}.{}' to '{}.{}'",
-                source_db, source_coll, dest_db, dest_coll
-            )
+            let filter = match &cli.filter_preset {
+                Some(_) => cli.preset_filter.clone(),
+                None => get_copy_filter()?,
+            };
+            let projection = match &cli.projection_file {
+                Some(projection) => Some(projection.clone()),
+                None => get_copy_projection()?,
+            };
+            if let Some(count) = cli.preview {
+                let options = copy_options(cli, &source_db, source_coll, &dest_db, &dest_coll)
+                    .with_limit(limit)
+                    .with_filter(filter.clone())
+                    .with_projection(projection.clone());
+                let documents = sample_documents(source, &options, count).await?;
+                print_sample_documents(&format!("{}.{}", source_db, source_coll), &documents);
+            }
+
+            let mut operation = if let Some(limit_val) = limit {
+                format!(
+                    "Copy {} documents from '{}.{}' to '{}.{}'",
+                    limit_val, source_db, source_coll, dest_db, dest_coll
+                )
+            } else {
+                format!(
+                    "Copy all documents from '{}.{}' to '{}.{}'",
+                    source_db, source_coll, dest_db, dest_coll
+                )
+            };
+            if let Some(percent) = cli.limit_percent {
+                operation.push_str(&format!(" ({}% of the collection)", percent));
+            }
+            if let Some(filter) = &filter {
+                operation.push_str(&format!(" matching {}", filter));
+            }
+            if let Some(stage) = &cli.match_stage {
+                operation.push_str(&format!(" with $match {}", stage));
+            }
+            if let Some(projection) = &projection {
+                operation.push_str(&format!(" with projection {}", projection));
+            }
+            if !cli.exclude_field.is_empty() {
+                operation.push_str(&format!(" excluding {}", cli.exclude_field.join(", ")));
+            }
+            if !cli.set_field.is_empty() {
+                let fields: Document = cli.set_field.iter().cloned().collect();
+                operation.push_str(&format!(" setting {}", fields));
+            }
+            if cli.dry_run {
+                break (limit, filter, projection);
+            }
+
+            match confirm_or_edit_operation(session, &source.uri, &dest.uri, &operation)? {
+                Confirmation::Proceed => break (limit, filter, projection),
+                Confirmation::Edit => continue,
+                Confirmation::Abort => {
+                    warn!(
+                        "Skipped collection '{}' - user declined confirmation",
+                        source_coll
+                    );
+                    info!("Skipped collection '{}'", source_coll);
+                    results.push(CopyResult::skipped(format!(
+                        "{}.{}",
+                        source_db, source_coll
+                    )));
+                    continue 'collections;
+                }
+            }
         };
 
-        if !confirm_operation(&source.uri, &dest.uri, &operation)? {
+        let options = copy_options(cli, &source_db, source_coll, &dest_db, &dest_coll)
+            .with_limit(limit)
+            .with_filter(filter)
+            .with_projection(projection)
+            .with_stop(Some(session.stop.clone()));
+        if cli.dry_run {
+            dry_run_plan.push(options);
+            continue;
+        }
+
+        ensure_source_collection(source, &source_db, source_coll).await?;
+
+        info!("Starting copy operation for collection '{}'", source_coll);
+        let entry = AuditEntry::start(
+            "collection",
+            &source.uri,
+            &dest.uri,
+            &format!("{}.{}", source_db, source_coll),
+            &format!("{}.{}", dest_db, dest_coll),
+        );
+        let stop_key = session.listen_for_stop();
+        let started = Instant::now();
+        let result = copy_collection(source, dest, &options)
+            .await
+            .map_err(copy_failure);
+        drop(stop_key);
+        audit::record(cli, &entry.finish(&result))?;
+        match result {
+            Ok(stats) => {
+                info!(
+                    "Copied {} documents ({}) from '{}.{}' to '{}.{}'",
+                    stats.documents,
+                    format_bytes(stats.bytes),
+                    source_db,
+                    source_coll,
+                    dest_db,
+                    dest_coll
+                );
+                results.push(CopyResult::copied(
+                    format!("{}.{}", source_db, source_coll),
+                    stats,
+                    started.elapsed(),
+                ));
+                session.record_copied(&source_db, source_coll);
+                verify_copy(source, dest, cli, &options).await?;
+            }
+            Err(e) => {
+                error!("Failed to copy collection '{}': {:#}", source_coll, e);
+                return Err(e);
+            }
+        }
+    }
+
+    if cli.dry_run {
+        print_copy_plan(source, dest, dry_run_plan, cli).await?;
+        info!("Dry run: no documents were copied");
+    }
+    Ok(results)
+}
+
+async fn handle_gridfs_copy(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    cli: &Cli,
+    session: &Session,
+) -> Result<Vec<CopyResult>> {
+    let source_db = select_source_database(source).await?;
+    debug!("Selected source database: '{}'", source_db);
+
+    let buckets = select_gridfs_buckets(source, &source_db).await?;
+    debug!("Selected {} GridFS bucket(s) for copying", buckets.len());
+
+    let dest_db = get_destination_database(
+        &default_dest_database(cli, &source_db),
+        cli.allow_system_dest,
+    )?;
+    debug!("Destination database: '{}'", dest_db);
+    let mut results = Vec::new();
+
+    for bucket in &buckets {
+        let operation = format!(
+            "Copy GridFS bucket '{}' from '{}' to '{}'",
+            bucket, source_db, dest_db
+        );
+
+        if cli.dry_run {
+            info!("Dry run: {}", operation);
+            continue;
+        }
+
+        if !confirm_operation(session, &source.uri, &dest.uri, &operation)? {
             warn!(
-                "Skipped collection '{}' - user declined confirmation",
-                source_coll
+                "Skipped GridFS bucket '{}' - user declined confirmation",
+                bucket
             );
-            info!("Skipped collection '{}'", source_coll);
+            info!("Skipped GridFS bucket '{}'", bucket);
+            results.push(CopyResult::skipped(format!("{}.{}", source_db, bucket)));
             continue;
         }
 
-        info!("Starting copy operation for collection '{}'", source_coll);
-        match copy_collection(
-            source,
-            dest,
-            &source_db,
+        info!("Starting copy operation for GridFS bucket '{}'", bucket);
+        // Only transfer settings: chunks and files must arrive unchanged
+        let template = CopyOptions::new(&source_db, "", &dest_db, "")
+            .with_max_docs_per_second(cli.max_docs_per_second)
+            .with_max_batch_bytes(cli.max_batch_bytes)
+            .with_max_batch_documents(cli.max_batch_documents.map(|n| n as usize))
+            .with_cursor_batch_size(cli.cursor_batch_size)
+            .with_stop(Some(session.stop.clone()));
+        let entry = AuditEntry::start(
+            "gridfs",
+            &source.uri,
+            &dest.uri,
+            &format!("{}.{}", source_db, bucket),
+            &format!("{}.{}", dest_db, bucket),
+        );
+        let stop_key = session.listen_for_stop();
+        let started = Instant::now();
+        let result = copy_gridfs_bucket(source, dest, &source_db, &dest_db, bucket, &template)
+            .await
+            .map_err(copy_failure);
+        drop(stop_key);
+        audit::record(cli, &entry.finish(&result))?;
+        match result {
+            Ok(stats) => {
+                info!(
+                    "GridFS bucket '{}' copied successfully ({} documents, {})",
+                    bucket,
+                    stats.documents,
+                    format_bytes(stats.bytes)
+                );
+                results.push(CopyResult::copied(
+                    format!("{}.{}", source_db, bucket),
+                    stats,
+                    started.elapsed(),
+                ));
+            }
+            Err(e) => {
+                error!("Failed to copy GridFS bucket '{}': {}", bucket, e);
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+async fn handle_namespace_copy(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    pattern: &str,
+    cli: &Cli,
+    session: &mut Session,
+) -> Result<usize> {
+    let dest_db = cli.dest_db.as_deref();
+    let (db_pattern, coll_pattern) = parse_namespace(pattern)?;
+
+    let mut matches = Vec::new();
+    for db in source.list_databases().await? {
+        if !glob_match(&db_pattern, &db) {
+            continue;
+        }
+        for coll in source.list_collections(&db).await? {
+            if glob_match(&coll_pattern, &coll) {
+                matches.push((db.clone(), coll));
+            }
+        }
+    }
+
+    info!(
+        "Namespace pattern '{}' matched {} collection(s)",
+        pattern,
+        matches.len()
+    );
+    if matches.is_empty() {
+        anyhow::bail!("No collections match '{}'", pattern);
+    }
+    let mut planned = Vec::new();
+    for (db, coll) in &matches {
+        let target_coll = dest_collection_name(cli, coll)?;
+        planned.push(copy_options(
+            cli,
+            db,
+            coll,
+            dest_db.unwrap_or(db),
+            &target_coll,
+        ));
+    }
+    let description = format!("matching '{}'", pattern);
+    run_copy_plan(source, dest, planned, &description, cli, session).await
+}
+
+/// Copy each `--copy` namespace, to its own target when one is given
+async fn handle_explicit_copy(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    specs: &[CopySpec],
+    cli: &Cli,
+    session: &mut Session,
+) -> Result<usize> {
+    let mut planned = Vec::new();
+    for spec in specs {
+        let (source_db, source_coll) = &spec.source;
+        ensure_source_collection(source, source_db, source_coll).await?;
+        let (dest_db, dest_coll) = match &spec.dest {
+            Some(target) => target.clone(),
+            None => (
+                cli.dest_db.clone().unwrap_or_else(|| source_db.clone()),
+                dest_collection_name(cli, source_coll)?,
+            ),
+        };
+        planned.push(copy_options(
+            cli,
+            source_db,
             source_coll,
             &dest_db,
             &dest_coll,
-            limit,
-        )
-        .await
-        {
-            Ok(count) => {
+        ));
+    }
+
+    let mut targets = HashSet::new();
+    for options in &planned {
+        if !targets.insert((options.dest_db.as_str(), options.dest_coll.as_str())) {
+            return Err(InvalidArgs(format!(
+                "'{}.{}' is the target of more than one --copy",
+                options.dest_db, options.dest_coll
+            ))
+            .into());
+        }
+    }
+
+    run_copy_plan(source, dest, planned, "given with --copy", cli, session).await
+}
+
+/// Build the plan for `planned` and print it in --plan-format
+async fn print_copy_plan(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    planned: Vec<CopyOptions>,
+    cli: &Cli,
+) -> Result<CopyPlan> {
+    let plan = build_plan(source, dest, planned).await?;
+    match cli.plan_format {
+        OutputFormat::Text => print_plan(&plan),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&plan)?),
+    }
+    warn_case_collisions(
+        "Destinations",
+        plan.entries.iter().map(|entry| entry.to.as_str()),
+    );
+    Ok(plan)
+}
+
+/// Plan `planned`, print the plan and, unless this is a dry run, copy each
+/// entry once confirmed; `description` names the collections in the
+/// confirmation summary. Returns the number of skipped operations.
+async fn run_copy_plan(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    planned: Vec<CopyOptions>,
+    description: &str,
+    cli: &Cli,
+    session: &mut Session,
+) -> Result<usize> {
+    let planned = planned
+        .into_iter()
+        .map(|options| options.with_stop(Some(session.stop.clone())))
+        .collect();
+    let plan = print_copy_plan(source, dest, planned, cli).await?;
+
+    if cli.dry_run {
+        info!("Dry run: no documents were copied");
+        return Ok(0);
+    }
+
+    let mut target_dbs: Vec<&str> = plan
+        .entries
+        .iter()
+        .map(|entry| entry.options.dest_db.as_str())
+        .collect();
+    target_dbs.sort_unstable();
+    target_dbs.dedup();
+    for target_db in target_dbs {
+        check_system_destination(cli, target_db)?;
+    }
+
+    let operation = format!(
+        "Copy {} collection(s) {} (~{} documents, see plan above)",
+        plan.entries.len(),
+        description,
+        plan.total_documents()
+    );
+    if !confirm_operation(session, &source.uri, &dest.uri, &operation)? {
+        warn!(
+            "Skipped collections {} - user declined confirmation",
+            description
+        );
+        return Ok(plan.entries.len());
+    }
+
+    for planned in &plan.entries {
+        let options = &planned.options;
+        let (source_db, coll) = (&options.source_db, &options.source_coll);
+        let (target_db, target_coll) = (&options.dest_db, &options.dest_coll);
+        ensure_source_collection(source, source_db, coll).await?;
+
+        let entry = AuditEntry::start(
+            "collection",
+            &source.uri,
+            &dest.uri,
+            &planned.from,
+            &planned.to,
+        );
+        let stop_key = session.listen_for_stop();
+        let result = copy_collection(source, dest, options)
+            .await
+            .map_err(copy_failure);
+        drop(stop_key);
+        audit::record(cli, &entry.finish(&result))?;
+        match result {
+            Ok(stats) => {
                 info!(
-                    "Copied {} documents from '{}.{}' to '{}.{}'",
-                    count, source_db, source_coll, dest_db, dest_coll
+                    "Copied {} documents ({}) from '{}.{}' to '{}.{}'",
+                    stats.documents,
+                    format_bytes(stats.bytes),
+                    source_db,
+                    coll,
+                    target_db,
+                    target_coll
                 );
+                session.record_copied(source_db, coll);
+                verify_copy(source, dest, cli, options).await?;
             }
             Err(e) => {
-                error!("Failed to copy collection '{}': {}", source_coll, e);
+                error!(
+                    "Failed to copy collection '{}.{}': {:#}",
+                    source_db, coll, e
+                );
                 return Err(e);
             }
         }
     }
 
+    Ok(0)
+}
+
+async fn handle_dump_restore(
+    dir: &Path,
+    dest: &MongoConnection,
+    cli: &Cli,
+    session: &Session,
+) -> Result<usize> {
+    let dumps = scan_dump(dir)?;
+    if dumps.is_empty() {
+        anyhow::bail!("No collections found in dump directory {:?}", dir);
+    }
+    let selected = select_dump_collections(&dumps)?;
+    debug!("Selected {} dumped collection(s)", selected.len());
+
+    let dest_db = cli.dest_db.as_deref();
+    let mut targets = Vec::new();
+    for dump in &selected {
+        let target = format!(
+            "{}.{}",
+            dest_db.unwrap_or(&dump.database),
+            dest_collection_name(cli, &dump.collection)?
+        );
+        info!("  {}.{} -> {}", dump.database, dump.collection, target);
+        targets.push(target);
+    }
+    warn_case_collisions("Destinations", targets.iter().map(String::as_str));
+
+    if cli.dry_run {
+        info!("Dry run: no documents were copied");
+        return Ok(0);
+    }
+
+    let mut target_dbs: Vec<&str> = selected
+        .iter()
+        .map(|d| dest_db.unwrap_or(&d.database))
+        .collect();
+    target_dbs.sort_unstable();
+    target_dbs.dedup();
+    for target_db in target_dbs {
+        check_system_destination(cli, target_db)?;
+    }
+    for dump in &selected {
+        check_namespace_length(
+            dest_db.unwrap_or(&dump.database),
+            &dest_collection_name(cli, &dump.collection)?,
+        )?;
+    }
+
+    let source_label = dir.display().to_string();
+    let operation = format!("Restore {} collection(s) from dump", selected.len());
+    if !confirm_operation(session, &source_label, &dest.uri, &operation)? {
+        warn!("Skipped dump restore - user declined confirmation");
+        return Ok(selected.len());
+    }
+
+    for dump in &selected {
+        let target_db = dest_db.unwrap_or(&dump.database);
+        let target_coll = dest_collection_name(cli, &dump.collection)?;
+        let options = copy_options(
+            cli,
+            &dump.database,
+            &dump.collection,
+            target_db,
+            &target_coll,
+        );
+        let entry = AuditEntry::start(
+            "dump",
+            &source_label,
+            &dest.uri,
+            &format!("{}.{}", dump.database, dump.collection),
+            &format!("{}.{}", target_db, target_coll),
+        );
+        let result = restore_dump_collection(dest, dump, &options).await;
+        audit::record(cli, &entry.finish(&result))?;
+        match result {
+            Ok(stats) => {
+                info!(
+                    "Restored {} documents ({}) from {:?} to '{}.{}'",
+                    stats.documents,
+                    format_bytes(stats.bytes),
+                    dump.bson_path,
+                    target_db,
+                    target_coll
+                );
+            }
+            Err(e) => {
+                error!(
+                    "Failed to restore '{}.{}': {:#}",
+                    dump.database, dump.collection, e
+                );
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(0)
+}
+
+/// Warn about plaintext connections to remote hosts, and in interactive runs
+/// ask before continuing
+fn check_plaintext(cli: &Cli, label: &str, uri: &str, interactive: bool) -> Result<()> {
+    if cli.allow_insecure || !is_plaintext_remote(uri)? {
+        return Ok(());
+    }
+    warn!(
+        "The {} connection to {} does not use TLS; credentials and data are sent in plain text (pass --allow-insecure to silence this)",
+        label,
+        mask_uri(uri)
+    );
+    if interactive && !confirm_plaintext_connection(label, uri)? {
+        anyhow::bail!("Declined to connect to the {} without TLS", label);
+    }
+    Ok(())
+}
+
+/// Refuse reserved system databases as a destination unless explicitly
+/// allowed and confirmed
+fn check_system_destination(cli: &Cli, database: &str) -> Result<()> {
+    if !is_system_database(database) {
+        return Ok(());
+    }
+    if !cli.allow_system_dest {
+        anyhow::bail!(
+            "Refusing to copy into reserved system database '{}'; pass --allow-system-dest to override",
+            database
+        );
+    }
+    if !confirm_system_destination(database)? {
+        anyhow::bail!("Declined to copy into system database '{}'", database);
+    }
     Ok(())
 }
 
-fn mask_uri(uri: &str) -> String {
-    if let Some(at_pos) = uri.find('@') {
-        if let Some(protocol_end) = uri.find("://") {
-            let protocol = &uri[..protocol_end + 3];
-            let after_at = &uri[at_pos..];
-            return format!("{}***{}", protocol, after_at);
+/// Catch collections dropped (or mistyped) between listing and copying
+async fn ensure_source_collection(
+    source: &MongoConnection,
+    database: &str,
+    collection: &str,
+) -> Result<()> {
+    if !source.collection_exists(database, collection).await? {
+        anyhow::bail!(
+            "Source collection '{}.{}' does not exist",
+            database,
+            collection
+        );
+    }
+    Ok(())
+}
+
+/// Run the --compare and --checksum checks asked for on a finished copy
+async fn verify_copy(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    cli: &Cli,
+    options: &CopyOptions,
+) -> Result<()> {
+    if cli.compare {
+        compare_and_print(source, dest, options).await?;
+    }
+    if cli.checksum {
+        let report = verify_checksums(source, dest, options, cli.checksum_sample).await?;
+        print_checksum_report(options, &report);
+        if !report.is_match() {
+            warn!(
+                "'{}.{}' has documents that differ from '{}.{}'",
+                options.dest_db, options.dest_coll, options.source_db, options.source_coll
+            );
         }
     }
-    uri.to_string()
+    Ok(())
+}
+
+async fn compare_and_print(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    options: &CopyOptions,
+) -> Result<()> {
+    let comparison = compare_collections(source, dest, options).await?;
+    print_comparison(options, &comparison);
+    if !comparison.is_match() {
+        warn!(
+            "'{}.{}' and '{}.{}' differ after copying",
+            options.source_db, options.source_coll, options.dest_db, options.dest_coll
+        );
+    }
+    Ok(())
 }
@@ -0,0 +1,184 @@
+use anyhow::{bail, Context, Result};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+static DOCUMENTS: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+static ERRORS: AtomicU64 = AtomicU64::new(0);
+
+/// Pushgateway job name used when the URL has no `/metrics/job/...` path
+const DEFAULT_JOB_PATH: &str = "/metrics/job/mongo_copy";
+
+/// Count documents and bytes written to a destination
+pub fn record_written(documents: u64, bytes: u64) {
+    DOCUMENTS.fetch_add(documents, Ordering::Relaxed);
+    BYTES.fetch_add(bytes, Ordering::Relaxed);
+}
+
+/// Count a failed collection copy
+pub fn record_error() {
+    ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Where metrics are pushed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetricsTarget {
+    /// `statsd://host:port`, sent as UDP datagrams
+    Statsd { addr: String },
+    /// `http://host:port[/metrics/job/<job>]`, a Prometheus pushgateway
+    Pushgateway { addr: String, path: String },
+}
+
+impl MetricsTarget {
+    pub fn parse(url: &str) -> Result<Self> {
+        if let Some(addr) = url.strip_prefix("statsd://") {
+            let addr = addr.trim_end_matches('/');
+            if addr.is_empty() {
+                bail!("StatsD URL '{}' has no host", url);
+            }
+            return Ok(Self::Statsd {
+                addr: addr.to_string(),
+            });
+        }
+        if let Some(rest) = url.strip_prefix("http://") {
+            let (addr, path) = match rest.find('/') {
+                Some(slash) => (&rest[..slash], &rest[slash..]),
+                None => (rest, ""),
+            };
+            if addr.is_empty() {
+                bail!("Pushgateway URL '{}' has no host", url);
+            }
+            let path = match path.trim_end_matches('/') {
+                "" => DEFAULT_JOB_PATH.to_string(),
+                path => path.to_string(),
+            };
+            return Ok(Self::Pushgateway {
+                addr: addr.to_string(),
+                path,
+            });
+        }
+        bail!(
+            "Metrics URL '{}' must start with statsd:// or http:// (a Prometheus pushgateway)",
+            url
+        )
+    }
+}
+
+/// Counter values at one moment, plus the rate since the previous push
+#[derive(Debug, Clone, Copy, Default)]
+struct Snapshot {
+    documents: u64,
+    bytes: u64,
+    errors: u64,
+    docs_per_second: f64,
+}
+
+/// Background task pushing copy counters and the current rate to StatsD or a
+/// Prometheus pushgateway every `interval` until finished
+pub struct MetricsExporter {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl MetricsExporter {
+    pub fn start(target: MetricsTarget, interval: Duration) -> Self {
+        debug!("Pushing metrics to {:?} every {:?}", target, interval);
+        let (stop, mut stopped) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let mut previous = Snapshot::default();
+            let mut last = Instant::now();
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                let done = tokio::select! {
+                    _ = ticker.tick() => false,
+                    _ = &mut stopped => true,
+                };
+                let current = snapshot(&previous, last.elapsed());
+                last = Instant::now();
+                if let Err(e) = push(&target, &previous, &current).await {
+                    warn!("Failed to push metrics: {:#}", e);
+                }
+                previous = current;
+                if done {
+                    break;
+                }
+            }
+        });
+        Self { stop, task }
+    }
+
+    /// Push the final values and stop
+    pub async fn finish(self) {
+        let _ = self.stop.send(());
+        let _ = self.task.await;
+    }
+}
+
+fn snapshot(previous: &Snapshot, elapsed: Duration) -> Snapshot {
+    let documents = DOCUMENTS.load(Ordering::Relaxed);
+    let seconds = elapsed.as_secs_f64();
+    Snapshot {
+        documents,
+        bytes: BYTES.load(Ordering::Relaxed),
+        errors: ERRORS.load(Ordering::Relaxed),
+        docs_per_second: if seconds > 0.0 {
+            documents.saturating_sub(previous.documents) as f64 / seconds
+        } else {
+            0.0
+        },
+    }
+}
+
+async fn push(target: &MetricsTarget, previous: &Snapshot, current: &Snapshot) -> Result<()> {
+    match target {
+        MetricsTarget::Statsd { addr } => {
+            // StatsD counters are deltas since the last push
+            let payload = format!(
+                "mongo_copy.documents:{}|c\nmongo_copy.bytes:{}|c\nmongo_copy.errors:{}|c\nmongo_copy.docs_per_second:{:.1}|g",
+                current.documents - previous.documents,
+                current.bytes - previous.bytes,
+                current.errors - previous.errors,
+                current.docs_per_second
+            );
+            let socket = UdpSocket::bind("0.0.0.0:0").await?;
+            socket
+                .send_to(payload.as_bytes(), addr.as_str())
+                .await
+                .with_context(|| format!("Failed to send to StatsD at {}", addr))?;
+        }
+        MetricsTarget::Pushgateway { addr, path } => {
+            let body = format!(
+                "# TYPE mongo_copy_documents_total counter\nmongo_copy_documents_total {}\n\
+                 # TYPE mongo_copy_bytes_total counter\nmongo_copy_bytes_total {}\n\
+                 # TYPE mongo_copy_errors_total counter\nmongo_copy_errors_total {}\n\
+                 # TYPE mongo_copy_docs_per_second gauge\nmongo_copy_docs_per_second {:.1}\n",
+                current.documents, current.bytes, current.errors, current.docs_per_second
+            );
+            let request = format!(
+                "PUT {} HTTP/1.1\r\nHost: {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                path,
+                addr,
+                body.len(),
+                body
+            );
+            let mut stream = TcpStream::connect(addr.as_str())
+                .await
+                .with_context(|| format!("Failed to connect to pushgateway at {}", addr))?;
+            stream.write_all(request.as_bytes()).await?;
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).await?;
+            let status = String::from_utf8_lossy(&response);
+            let status = status.lines().next().unwrap_or_default();
+            if !status.contains(" 200 ") && !status.contains(" 202 ") {
+                bail!("Pushgateway at {} replied '{}'", addr, status);
+            }
+        }
+    }
+    Ok(())
+}
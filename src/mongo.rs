@@ -1,46 +1,847 @@
 use anyhow::{Context, Result};
-use futures::stream::TryStreamExt;
+use futures::future::BoxFuture;
+use futures::stream::{self, StreamExt, TryStream, TryStreamExt};
 use mongodb::{
-    bson::{doc, Document},
-    options::ClientOptions,
-    Client, Database,
+    action::{Aggregate, Find},
+    bson::{doc, Bson, DateTime, Document},
+    options::{
+        ClientOptions, Hint, IndexOptions, ReadPreference, ReadPreferenceOptions,
+        SelectionCriteria, TagSet,
+    },
+    Client, ClientSession, Collection, Cursor, Database, IndexModel, SessionCursor,
 };
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use tracing::{debug, error, info, warn};
 
+use crate::error::CopyError;
+use crate::format_bytes;
+use crate::namespace::{check_namespace_length, is_system_database};
+use crate::resume::ResumeState;
+use crate::throttle::RateLimiter;
+
+/// Largest number of documents a transactional copy will attempt
+pub const MAX_TRANSACTION_DOCUMENTS: u64 = 10_000;
+/// Largest volume a transactional copy will attempt, kept well inside the
+/// server's transaction lifetime and cache limits
+pub const MAX_TRANSACTION_BYTES: u64 = 16 * 1024 * 1024;
+/// Default number of documents at which a write batch is flushed
+pub const DEFAULT_MAX_BATCH_DOCUMENTS: usize = 1000;
+/// Default serialized size at which a write batch is flushed, matching the
+/// server's 16MB BSON document limit
+pub const DEFAULT_MAX_BATCH_BYTES: u64 = 16 * 1024 * 1024;
+/// Largest write the server accepts in one message (`maxMessageSizeBytes`),
+/// batches are always split below this whatever `max_batch_bytes` says
+pub const MAX_WRITE_BATCH_BYTES: u64 = 48_000_000;
+
+/// Collections counted at once when listing many
+pub(crate) const COUNT_CONCURRENCY: usize = 16;
+
+/// How copied documents are written to the destination collection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WriteStrategy {
+    /// Insert documents, failing on duplicate `_id`s
+    #[default]
+    Insert,
+    /// Replace documents with a matching `_id`, inserting the rest
+    Upsert,
+    /// Insert only documents whose `_id` is not at the destination yet,
+    /// leaving existing ones untouched
+    #[serde(rename = "missing-only")]
+    MissingOnly,
+}
+
+/// What to do with a source document larger than `max_document_bytes`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum OversizedPolicy {
+    /// Stop the copy with an error
+    #[default]
+    Fail,
+    /// Log the document's `_id` and carry on without it
+    Skip,
+}
+
+/// Read concern level for reads from the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ReadConcern {
+    Local,
+    Majority,
+    /// Needs MongoDB 5.0+ and applies only to finds and aggregations; each
+    /// cursor must finish within the server's snapshot history window
+    Snapshot,
+}
+
+impl From<ReadConcern> for mongodb::options::ReadConcern {
+    fn from(level: ReadConcern) -> Self {
+        match level {
+            ReadConcern::Local => Self::local(),
+            ReadConcern::Majority => Self::majority(),
+            ReadConcern::Snapshot => Self::snapshot(),
+        }
+    }
+}
+
+/// How [`MongoConnection::connect`] sets up a client
+#[derive(Debug, Clone, Default)]
+pub struct ConnectOptions {
+    /// Read at this level instead of any level in the URI
+    pub read_concern: Option<ReadConcern>,
+    /// Only read from replica set members with all of these tags, e.g.
+    /// `region=us-east`
+    pub read_tags: Vec<(String, String)>,
+    /// Don't ping the server to check the connection, leaving errors to
+    /// the first real operation
+    pub skip_test: bool,
+}
+
+impl ConnectOptions {
+    pub fn with_read_concern(mut self, read_concern: Option<ReadConcern>) -> Self {
+        self.read_concern = read_concern;
+        self
+    }
+
+    pub fn with_read_tags(mut self, read_tags: Vec<(String, String)>) -> Self {
+        self.read_tags = read_tags;
+        self
+    }
+
+    pub fn with_skip_test(mut self, skip_test: bool) -> Self {
+        self.skip_test = skip_test;
+        self
+    }
+}
+
+/// How a database copy treats collections that already exist at the
+/// destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ConflictPolicy {
+    /// Leave the existing collection alone and move on
+    Skip,
+    /// Drop the existing collection, then copy
+    Overwrite,
+    /// Upsert into the existing collection by `_id`
+    Merge,
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConflictPolicy::Skip => write!(f, "skip existing collections"),
+            ConflictPolicy::Overwrite => write!(f, "drop and overwrite existing collections"),
+            ConflictPolicy::Merge => write!(f, "merge (upsert) into existing collections"),
+        }
+    }
+}
+
+/// A TTL index to ensure on the destination so copied documents expire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TtlIndex {
+    /// Date field the expiry counts from
+    pub field: String,
+    pub expire_after: Duration,
+}
+
+/// Only copy documents whose `field` date is at or after `start`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SinceFilter {
+    pub field: String,
+    pub start: DateTime,
+}
+
+/// A slice of a database's collections ranked by data size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectionSlice {
+    Largest(usize),
+    Smallest(usize),
+}
+
+/// What to copy and how, for a single collection
+#[derive(Debug, Clone, Default)]
+pub struct CopyOptions {
+    pub source_db: String,
+    pub source_coll: String,
+    pub dest_db: String,
+    pub dest_coll: String,
+    /// Maximum number of documents to copy, all when `None`
+    pub limit: Option<u64>,
+    /// Find filter applied to the source collection
+    pub filter: Option<Document>,
+    pub write_strategy: WriteStrategy,
+    /// Write all documents in a single destination transaction
+    pub transactional: bool,
+    /// Shard the destination collection on this key before copying
+    pub shard_key: Option<Document>,
+    /// Inclusive lower bound on `_id`
+    pub id_min: Option<Bson>,
+    /// Exclusive upper bound on `_id`
+    pub id_max: Option<Bson>,
+    /// Lower bound on a date field, combined with `filter`
+    pub since: Option<SinceFilter>,
+    /// Projection applied to the source find
+    pub projection: Option<Document>,
+    /// Fields (dotted paths allowed) excluded from every source document
+    /// on top of `projection`
+    pub exclude_fields: Vec<String>,
+    /// Documents whose serialized size exceeds this are handled by
+    /// `oversized_policy`
+    pub max_document_bytes: Option<u64>,
+    pub oversized_policy: OversizedPolicy,
+    /// Skip the destination collection's document validator
+    pub bypass_validation: bool,
+    /// Throttle reads and writes to at most this many documents per second
+    pub max_docs_per_second: Option<u64>,
+    /// Flush a write batch before its serialized size exceeds this,
+    /// [`DEFAULT_MAX_BATCH_BYTES`] when `None`
+    pub max_batch_bytes: Option<u64>,
+    /// Flush a write batch once it holds this many documents,
+    /// [`DEFAULT_MAX_BATCH_DOCUMENTS`] when `None`
+    pub max_batch_documents: Option<usize>,
+    /// Documents the server returns per round trip on the source cursor,
+    /// the server default when `None`
+    pub cursor_batch_size: Option<u32>,
+    /// Keep the server from reaping an idle source cursor
+    pub no_cursor_timeout: bool,
+    /// Index the source find should use
+    pub hint: Option<Hint>,
+    /// TTL index ensured on the destination before copying
+    pub ttl: Option<TtlIndex>,
+    /// Read the source through a snapshot session, shared by every
+    /// collection of a [`copy_collections`] run
+    pub snapshot: bool,
+    /// Checked after every write batch; once raised the copy fails with
+    /// [`Stopped`]
+    pub stop: Option<StopSignal>,
+    /// Fields (dotted paths allowed) matching upserted documents to existing
+    /// ones, `_id` when empty
+    pub upsert_key: Vec<String>,
+    /// Append a JSON line for each skipped document to this file
+    pub skipped_report: Option<PathBuf>,
+    /// Directory where [`copy_collections`] records which collections have
+    /// completed, so a failed run can be resumed
+    pub progress_dir: Option<PathBuf>,
+    /// Skip collections recorded as completed by a previous failed run
+    pub resume: bool,
+    /// Build only the dumped indexes with these names, all when empty
+    pub indexes: Vec<String>,
+    /// Don't build the dumped indexes with these names
+    pub skip_indexes: Vec<String>,
+    /// Read the source in `_id` order and reopen the find after the last
+    /// `_id` read when the server kills the cursor
+    pub auto_resume_cursor: bool,
+    /// `create` options for the destination collection, used instead of
+    /// the source collection's
+    pub dest_collection_options: Option<Document>,
+    /// Top-level fields added to every document before it is written
+    pub set_fields: Document,
+    /// Replace fields from `set_fields` the source document already has,
+    /// instead of keeping the source value
+    pub overwrite_set_fields: bool,
+    /// Read the source in `$natural` (insertion) order. Set automatically
+    /// for capped source collections.
+    pub natural_order: bool,
+    /// Read the source with an aggregation, `$match` on this (combined with
+    /// the other filters) followed by `$limit`, instead of a find
+    pub match_stage: Option<Document>,
+    /// Insert batches unordered: the server may apply a batch's documents in
+    /// any order and keeps inserting past a failed one
+    pub unordered: bool,
+    /// Copy this percentage of the source's estimated document count,
+    /// rounded up, resolved into a limit by [`resolve_limit_percent`].
+    /// Ignored when `limit` is set.
+    pub limit_percent: Option<f64>,
+}
+
+impl CopyOptions {
+    pub fn new(source_db: &str, source_coll: &str, dest_db: &str, dest_coll: &str) -> Self {
+        Self {
+            source_db: source_db.to_string(),
+            source_coll: source_coll.to_string(),
+            dest_db: dest_db.to_string(),
+            dest_coll: dest_coll.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_limit(mut self, limit: Option<u64>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn with_limit_percent(mut self, limit_percent: Option<f64>) -> Self {
+        self.limit_percent = limit_percent;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: Option<Document>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_write_strategy(mut self, write_strategy: WriteStrategy) -> Self {
+        self.write_strategy = write_strategy;
+        self
+    }
+
+    pub fn with_transactional(mut self, transactional: bool) -> Self {
+        self.transactional = transactional;
+        self
+    }
+
+    pub fn with_shard_key(mut self, shard_key: Option<Document>) -> Self {
+        self.shard_key = shard_key;
+        self
+    }
+
+    /// Restrict the copy to `min <= _id < max`; either bound may be open
+    pub fn with_id_range(mut self, min: Option<Bson>, max: Option<Bson>) -> Self {
+        self.id_min = min;
+        self.id_max = max;
+        self
+    }
+
+    pub fn with_since(mut self, since: Option<SinceFilter>) -> Self {
+        self.since = since;
+        self
+    }
+
+    pub fn with_projection(mut self, projection: Option<Document>) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    pub fn with_exclude_fields(mut self, exclude_fields: Vec<String>) -> Self {
+        self.exclude_fields = exclude_fields;
+        self
+    }
+
+    pub fn with_max_document_bytes(
+        mut self,
+        max_document_bytes: Option<u64>,
+        policy: OversizedPolicy,
+    ) -> Self {
+        self.max_document_bytes = max_document_bytes;
+        self.oversized_policy = policy;
+        self
+    }
+
+    pub fn with_unordered(mut self, unordered: bool) -> Self {
+        self.unordered = unordered;
+        self
+    }
+
+    pub fn with_bypass_validation(mut self, bypass_validation: bool) -> Self {
+        self.bypass_validation = bypass_validation;
+        self
+    }
+
+    pub fn with_max_docs_per_second(mut self, max_docs_per_second: Option<u64>) -> Self {
+        self.max_docs_per_second = max_docs_per_second;
+        self
+    }
+
+    pub fn with_max_batch_bytes(mut self, max_batch_bytes: Option<u64>) -> Self {
+        self.max_batch_bytes = max_batch_bytes;
+        self
+    }
+
+    pub fn with_max_batch_documents(mut self, max_batch_documents: Option<usize>) -> Self {
+        self.max_batch_documents = max_batch_documents;
+        self
+    }
+
+    pub fn with_cursor_batch_size(mut self, cursor_batch_size: Option<u32>) -> Self {
+        self.cursor_batch_size = cursor_batch_size;
+        self
+    }
+
+    pub fn with_no_cursor_timeout(mut self, no_cursor_timeout: bool) -> Self {
+        self.no_cursor_timeout = no_cursor_timeout;
+        self
+    }
+
+    pub fn with_hint(mut self, hint: Option<Hint>) -> Self {
+        self.hint = hint;
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: Option<TtlIndex>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn with_snapshot(mut self, snapshot: bool) -> Self {
+        self.snapshot = snapshot;
+        self
+    }
+
+    pub fn with_stop(mut self, stop: Option<StopSignal>) -> Self {
+        self.stop = stop;
+        self
+    }
+
+    pub fn with_upsert_key(mut self, upsert_key: Vec<String>) -> Self {
+        self.upsert_key = upsert_key;
+        self
+    }
+
+    pub fn with_skipped_report(mut self, path: Option<PathBuf>) -> Self {
+        self.skipped_report = path;
+        self
+    }
+
+    pub fn with_progress_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.progress_dir = dir;
+        self
+    }
+
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    pub fn with_indexes(mut self, indexes: Vec<String>, skip_indexes: Vec<String>) -> Self {
+        self.indexes = indexes;
+        self.skip_indexes = skip_indexes;
+        self
+    }
+
+    pub fn with_auto_resume_cursor(mut self, auto_resume_cursor: bool) -> Self {
+        self.auto_resume_cursor = auto_resume_cursor;
+        self
+    }
+
+    pub fn with_dest_collection_options(mut self, options: Option<Document>) -> Self {
+        self.dest_collection_options = options;
+        self
+    }
+
+    pub fn with_set_fields(mut self, fields: Document, overwrite: bool) -> Self {
+        self.set_fields = fields;
+        self.overwrite_set_fields = overwrite;
+        self
+    }
+
+    pub fn with_match_stage(mut self, match_stage: Option<Document>) -> Self {
+        self.match_stage = match_stage;
+        self
+    }
+
+    fn stop_requested(&self) -> bool {
+        self.stop.as_ref().is_some_and(StopSignal::is_requested)
+    }
+
+    /// The projection combining `projection` with `exclude_fields`
+    pub fn effective_projection(&self) -> Option<Document> {
+        if self.exclude_fields.is_empty() {
+            return self.projection.clone();
+        }
+        let mut projection = self.projection.clone().unwrap_or_default();
+        for field in &self.exclude_fields {
+            projection.insert(field.clone(), 0);
+        }
+        Some(projection)
+    }
+
+    /// The find filter combining `filter` and `match_stage` with any `_id`
+    /// range and `since`
+    pub fn effective_filter(&self) -> Document {
+        let mut clauses = Vec::new();
+        for filter in [&self.filter, &self.match_stage].into_iter().flatten() {
+            if !filter.is_empty() {
+                clauses.push(filter.clone());
+            }
+        }
+
+        let mut id_range = Document::new();
+        if let Some(min) = &self.id_min {
+            id_range.insert("$gte", min.clone());
+        }
+        if let Some(max) = &self.id_max {
+            id_range.insert("$lt", max.clone());
+        }
+        if !id_range.is_empty() {
+            clauses.push(doc! { "_id": id_range });
+        }
+        if let Some(since) = &self.since {
+            clauses.push(doc! { since.field.as_str(): { "$gte": since.start } });
+        }
+
+        match clauses.len() {
+            0 => Document::new(),
+            1 => clauses.remove(0),
+            _ => doc! { "$and": clauses },
+        }
+    }
+}
+
+/// Check that `projection` does not mix included and excluded fields; `_id`
+/// may be excluded from an inclusion projection, and operators such as
+/// `$slice` are allowed in either kind
+pub fn validate_projection(projection: &Document) -> Result<()> {
+    let mut included = Vec::new();
+    let mut excluded = Vec::new();
+    classify_projection(projection, "", &mut included, &mut excluded);
+
+    if !included.is_empty() && !excluded.is_empty() {
+        anyhow::bail!(
+            "Projection cannot mix inclusion ({}) and exclusion ({}) except for _id",
+            included.join(", "),
+            excluded.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn classify_projection(
+    projection: &Document,
+    prefix: &str,
+    included: &mut Vec<String>,
+    excluded: &mut Vec<String>,
+) {
+    for (key, value) in projection {
+        let path = format!("{}{}", prefix, key);
+        let include = match value {
+            Bson::Boolean(b) => Some(*b),
+            Bson::Int32(n) => Some(*n != 0),
+            Bson::Int64(n) => Some(*n != 0),
+            Bson::Double(n) => Some(*n != 0.0),
+            // Nested form, e.g. {"address": {"city": 1}}
+            Bson::Document(nested) if !nested.keys().any(|k| k.starts_with('$')) => {
+                classify_projection(nested, &format!("{}.", path), included, excluded);
+                None
+            }
+            _ => None,
+        };
+        match include {
+            Some(_) if path == "_id" => {}
+            Some(true) => included.push(path),
+            Some(false) => excluded.push(path),
+            None => {}
+        }
+    }
+}
+
+/// Check that `options` holds only `create` command options of the right
+/// types, so a mistake is caught before anything is copied
+pub fn validate_collection_options(options: &Document) -> Result<()> {
+    for (name, value) in options {
+        let valid = match name.as_str() {
+            "capped" => matches!(value, Bson::Boolean(_)),
+            "size" | "max" | "expireAfterSeconds" => {
+                value.as_i32().map(i64::from).or(value.as_i64()).is_some_and(|n| n > 0)
+                    || value.as_f64().is_some_and(|n| n > 0.0)
+            }
+            "validationLevel" => {
+                matches!(value.as_str(), Some("off" | "strict" | "moderate"))
+            }
+            "validationAction" => {
+                matches!(value.as_str(), Some("error" | "warn" | "errorAndLog"))
+            }
+            "validator" | "collation" | "clusteredIndex" | "timeseries"
+            | "changeStreamPreAndPostImages" | "storageEngine" | "indexOptionDefaults" => {
+                matches!(value, Bson::Document(_))
+            }
+            _ => anyhow::bail!(
+                "'{}' is not a supported collection option (expected capped, size, max, validator, validationLevel, validationAction, collation, clusteredIndex, expireAfterSeconds, timeseries, changeStreamPreAndPostImages, storageEngine or indexOptionDefaults)",
+                name
+            ),
+        };
+        if !valid {
+            anyhow::bail!(
+                "Collection option '{}' has an invalid value {}",
+                name,
+                value
+            );
+        }
+    }
+    if options.get_bool("capped").unwrap_or(false) && !options.contains_key("size") {
+        anyhow::bail!("A capped collection needs a 'size' in bytes");
+    }
+    Ok(())
+}
+
+/// Totals for a completed copy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CopyStats {
+    pub documents: u64,
+    /// Serialized BSON size of the copied documents
+    pub bytes: u64,
+    /// Oversized documents left out under [`OversizedPolicy::Skip`]
+    pub skipped: u64,
+}
+
+impl CopyStats {
+    pub fn add(&mut self, other: CopyStats) {
+        self.documents += other.documents;
+        self.bytes += other.bytes;
+        self.skipped += other.skipped;
+    }
+}
+
+/// Whether an operation of a run was carried out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyStatus {
+    Copied,
+    /// Declined at confirmation
+    Skipped,
+}
+
+/// The outcome of one operation of a run (a database, collection or GridFS
+/// bucket), for summaries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyResult {
+    /// Source namespace, `database` or `database.collection`
+    pub namespace: String,
+    pub stats: CopyStats,
+    pub status: CopyStatus,
+    pub duration: Duration,
+}
+
+impl CopyResult {
+    pub fn copied(namespace: impl Into<String>, stats: CopyStats, duration: Duration) -> Self {
+        Self {
+            namespace: namespace.into(),
+            stats,
+            status: CopyStatus::Copied,
+            duration,
+        }
+    }
+
+    pub fn skipped(namespace: impl Into<String>) -> Self {
+        Self {
+            namespace: namespace.into(),
+            stats: CopyStats::default(),
+            status: CopyStatus::Skipped,
+            duration: Duration::ZERO,
+        }
+    }
+}
+
+/// Context on a failed copy's error recording what had already been written
+/// to the destination, so the caller can report how far it got
+#[derive(Debug, Clone, Copy)]
+pub struct PartialCopy(pub CopyStats);
+
+impl fmt::Display for PartialCopy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} documents ({}) were copied before the failure",
+            self.0.documents,
+            format_bytes(self.0.bytes)
+        )
+    }
+}
+
+impl std::error::Error for PartialCopy {}
+
+/// Shared flag asking running copies to stop once their current batch is
+/// written
+#[derive(Debug, Clone, Default)]
+pub struct StopSignal(Arc<AtomicBool>);
+
+impl StopSignal {
+    pub fn request(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Error for a copy that ended early because its [`StopSignal`] was raised
+#[derive(Debug)]
+pub struct Stopped;
+
+impl fmt::Display for Stopped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Copy stopped on request")
+    }
+}
+
+impl std::error::Error for Stopped {}
+
+/// Size and shape of a collection, as reported by `collStats`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CollectionStats {
+    pub documents: u64,
+    pub indexes: u64,
+    /// Average document size in bytes
+    pub avg_document_size: f64,
+    /// Uncompressed size of the documents in bytes
+    pub size: u64,
+    /// Space allocated on disk for the documents in bytes
+    pub storage_size: u64,
+}
+
+/// Source and destination statistics for a copied collection
+#[derive(Debug, Clone)]
+pub struct CollectionComparison {
+    pub source: CollectionStats,
+    pub dest: CollectionStats,
+}
+
+impl CollectionComparison {
+    pub fn documents_match(&self) -> bool {
+        self.source.documents == self.dest.documents
+    }
+
+    pub fn indexes_match(&self) -> bool {
+        self.source.indexes == self.dest.indexes
+    }
+
+    pub fn avg_document_size_matches(&self) -> bool {
+        // Sizes are averages, so allow for rounding on either side
+        (self.source.avg_document_size - self.dest.avg_document_size).abs() < 1.0
+    }
+
+    pub fn is_match(&self) -> bool {
+        self.documents_match() && self.indexes_match() && self.avg_document_size_matches()
+    }
+}
+
+/// The URI's read preference restricted to members with `tags`. Tags can't
+/// pick the primary, so without a read preference a tagged secondary is read.
+fn tagged_read_preference(
+    criteria: Option<SelectionCriteria>,
+    tags: &[(String, String)],
+) -> Result<ReadPreference> {
+    let mut tag_set = TagSet::new();
+    for (key, value) in tags {
+        if tag_set.insert(key.clone(), value.clone()).is_some() {
+            anyhow::bail!("Read tag '{}' is given more than once", key);
+        }
+    }
+    let tagged = |options: Option<ReadPreferenceOptions>| {
+        let mut options = options.unwrap_or_default();
+        options.tag_sets = Some(vec![tag_set]);
+        Some(options)
+    };
+    Ok(match criteria {
+        None => ReadPreference::Secondary {
+            options: tagged(None),
+        },
+        Some(SelectionCriteria::ReadPreference(read_preference)) => match read_preference {
+            ReadPreference::Primary => {
+                anyhow::bail!("Read tags can't be used with readPreference=primary in the URI")
+            }
+            ReadPreference::Secondary { options } => ReadPreference::Secondary {
+                options: tagged(options),
+            },
+            ReadPreference::PrimaryPreferred { options } => ReadPreference::PrimaryPreferred {
+                options: tagged(options),
+            },
+            ReadPreference::SecondaryPreferred { options } => ReadPreference::SecondaryPreferred {
+                options: tagged(options),
+            },
+            ReadPreference::Nearest { options } => ReadPreference::Nearest {
+                options: tagged(options),
+            },
+            other => anyhow::bail!("Read tags can't be used with read preference {}", other),
+        },
+        Some(_) => anyhow::bail!("Read tags need a read preference mode"),
+    })
+}
+
 pub struct MongoConnection {
     pub client: Client,
     pub uri: String,
 }
 
 impl MongoConnection {
-    pub async fn new(uri: &str) -> Result<Self> {
+    pub async fn new(uri: &str) -> Result<Self, CopyError> {
+        Self::connect(uri, ConnectOptions::default()).await
+    }
+
+    pub async fn connect(uri: &str, options: ConnectOptions) -> Result<Self, CopyError> {
         debug!("Parsing MongoDB URI");
         let mut client_options = ClientOptions::parse(uri)
             .await
             .context("Failed to parse MongoDB URI")?;
         client_options.app_name = Some("mongo-copy".to_string());
         debug!("MongoDB client options configured: app_name=mongo-copy");
+        if let Some(level) = options.read_concern {
+            client_options.read_concern = Some(level.into());
+        }
+        match client_options
+            .read_concern
+            .as_ref()
+            .and_then(|concern| mongodb::bson::to_document(concern).ok())
+        {
+            Some(concern) => info!(
+                "Read concern: {}",
+                concern.get_str("level").unwrap_or("unknown")
+            ),
+            None => debug!("Read concern: server default"),
+        }
+        if !options.read_tags.is_empty() {
+            let read_preference = tagged_read_preference(
+                client_options.selection_criteria.take(),
+                &options.read_tags,
+            )?;
+            info!("Read preference: {}", read_preference);
+            client_options.selection_criteria =
+                Some(SelectionCriteria::ReadPreference(read_preference));
+        }
 
         debug!("Creating MongoDB client");
         let client =
             Client::with_options(client_options).context("Failed to create MongoDB client")?;
 
+        if options.skip_test {
+            info!("Skipping the connection test, connection errors will surface during the copy");
+            return Ok(Self {
+                client,
+                uri: uri.to_string(),
+            });
+        }
+
         // Test connection
-        debug!("Testing MongoDB connection by listing databases");
-        match client.list_database_names().await {
-            Ok(_) => {
+        debug!("Testing MongoDB connection with ping");
+        let test = match client
+            .database("admin")
+            .run_command(doc! { "ping": 1 })
+            .await
+        {
+            // Some proxies don't implement ping; bad credentials also fail
+            // as a command error, but listing won't get past them either
+            Err(e) if is_command_error(&e) && connection_failure_hint(&e).is_none() => {
+                debug!("Ping failed ({}), testing by listing databases", e);
+                client.list_database_names().await.map(|_| ())
+            }
+            result => result.map(|_| ()),
+        };
+        match test {
+            Ok(()) => {
                 debug!("MongoDB connection test successful");
             }
             Err(e) => {
-                match e.kind.as_ref() {
-                    mongodb::error::ErrorKind::ServerSelection { .. } => {
-                        info!("Likely, the URI needs to include the `directConnection=true` parameter.");
-                    }
-                    _ => {
-                        error!("MongoDB connection test failed: {}", e);
+                error!("MongoDB connection test failed: {}", e);
+                let error = match connection_failure_hint(&e) {
+                    Some(hint) => {
+                        info!("{}", hint);
+                        anyhow::Error::new(e)
+                            .context(format!("Failed to connect to MongoDB: {}", hint))
                     }
-                }
-                return Err(e).context("Failed to connect to MongoDB");
+                    None => anyhow::Error::new(e).context("Failed to connect to MongoDB"),
+                };
+                return Err(error.into());
             }
         }
 
@@ -50,17 +851,62 @@ impl MongoConnection {
         })
     }
 
+    /// Connect, retrying up to `retries` more times with exponential backoff
+    pub async fn connect_with_retries(
+        uri: &str,
+        retries: u32,
+        options: ConnectOptions,
+    ) -> Result<Self, CopyError> {
+        let mut attempt = 0;
+        loop {
+            match Self::connect(uri, options.clone()).await {
+                Ok(conn) => return Ok(conn),
+                // Wrong credentials won't fix themselves, and retrying risks
+                // locking the account
+                Err(e) if attempt < retries && !matches!(e, CopyError::Authentication(_)) => {
+                    let delay = Duration::from_secs(1u64 << attempt.min(5));
+                    attempt += 1;
+                    warn!(
+                        "Connection attempt {}/{} failed: {:#}; retrying in {}s",
+                        attempt,
+                        retries + 1,
+                        e,
+                        delay.as_secs()
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// The server's version string, from `buildInfo`
+    pub async fn server_version(&self) -> Result<String> {
+        let info = self
+            .client
+            .database("admin")
+            .run_command(doc! { "buildInfo": 1 })
+            .await
+            .context("Failed to read server build info")?;
+        Ok(info.get_str("version").unwrap_or("unknown").to_string())
+    }
+
+    /// Database names sorted case-insensitively, with the system databases
+    /// last
     pub async fn list_databases(&self) -> Result<Vec<String>> {
         debug!("Listing databases");
-        let databases = self.client.list_database_names().await?;
+        let mut databases = self.client.list_database_names().await?;
+        databases.sort_by_cached_key(|name| (is_system_database(name), name.to_lowercase()));
         debug!("Found {} databases", databases.len());
         Ok(databases)
     }
 
+    /// Collection names sorted case-insensitively
     pub async fn list_collections(&self, database_name: &str) -> Result<Vec<String>> {
         debug!("Listing collections in database '{}'", database_name);
         let db = self.client.database(database_name);
-        let collections = db.list_collection_names().await?;
+        let mut collections = db.list_collection_names().await?;
+        collections.sort_by_cached_key(|name| name.to_lowercase());
         debug!(
             "Found {} collections in database '{}'",
             collections.len(),
@@ -69,6 +915,118 @@ impl MongoConnection {
         Ok(collections)
     }
 
+    pub async fn collection_stats(
+        &self,
+        database: &str,
+        collection: &str,
+    ) -> Result<CollectionStats> {
+        debug!("Getting collection stats for '{}.{}'", database, collection);
+        let db = self.client.database(database);
+
+        let stats = match db.run_command(doc! { "collStats": collection }).await {
+            Ok(stats) => stats,
+            Err(e) if is_namespace_not_found(&e) => {
+                debug!("Collection '{}.{}' does not exist", database, collection);
+                return Ok(CollectionStats::default());
+            }
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to get stats for '{}.{}'", database, collection)
+                })
+            }
+        };
+
+        let indexes = match db
+            .collection::<Document>(collection)
+            .list_index_names()
+            .await
+        {
+            Ok(names) => names.len() as u64,
+            Err(e) if is_namespace_not_found(&e) => 0,
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(CollectionStats {
+            documents: number_field(&stats, "count") as u64,
+            indexes,
+            avg_document_size: number_field(&stats, "avgObjSize"),
+            size: number_field(&stats, "size") as u64,
+            storage_size: number_field(&stats, "storageSize") as u64,
+        })
+    }
+
+    /// List GridFS bucket names, i.e. prefixes with both a `.files` and a
+    /// `.chunks` collection
+    pub async fn list_gridfs_buckets(&self, database: &str) -> Result<Vec<String>> {
+        let collections = self.list_collections(database).await?;
+        let buckets: Vec<String> = collections
+            .iter()
+            .filter_map(|c| c.strip_suffix(".files"))
+            .filter(|bucket| collections.contains(&format!("{}.chunks", bucket)))
+            .map(String::from)
+            .collect();
+        debug!(
+            "Found {} GridFS bucket(s) in database '{}'",
+            buckets.len(),
+            database
+        );
+        Ok(buckets)
+    }
+
+    pub async fn database_exists(&self, database: &str) -> Result<bool> {
+        debug!("Checking whether database '{}' exists", database);
+        let names = self
+            .client
+            .list_database_names()
+            .filter(doc! { "name": database })
+            .await?;
+        Ok(!names.is_empty())
+    }
+
+    pub async fn collection_exists(&self, database: &str, collection: &str) -> Result<bool> {
+        debug!("Checking whether '{}.{}' exists", database, collection);
+        let names = self
+            .client
+            .database(database)
+            .list_collection_names()
+            .filter(doc! { "name": collection })
+            .await?;
+        Ok(!names.is_empty())
+    }
+
+    /// Whether `database.collection` exists and is a view
+    pub async fn is_view(&self, database: &str, collection: &str) -> Result<bool> {
+        Ok(self
+            .collection_spec(database, collection)
+            .await?
+            .is_some_and(|spec| spec.get_str("type").ok() == Some("view")))
+    }
+
+    /// The raw `listCollections` entry for a collection (name, type,
+    /// options, info), or `None` if it doesn't exist
+    pub async fn collection_spec(
+        &self,
+        database: &str,
+        collection: &str,
+    ) -> Result<Option<Document>> {
+        debug!("Reading collection spec for '{}.{}'", database, collection);
+        let reply = self
+            .client
+            .database(database)
+            .run_command(doc! {
+                "listCollections": 1,
+                "filter": { "name": collection },
+            })
+            .await?;
+        Ok(reply
+            .get_document("cursor")
+            .ok()
+            .and_then(|cursor| cursor.get_array("firstBatch").ok())
+            .and_then(|batch| batch.first())
+            .and_then(Bson::as_document)
+            .cloned())
+    }
+
     pub fn get_database(&self, name: &str) -> Database {
         debug!("Getting database handle for '{}'", name);
         self.client.database(name)
@@ -95,94 +1053,1808 @@ impl MongoConnection {
             }
         }
     }
+
+    /// Estimated document count for display, or `None` when the estimate is
+    /// zero but the collection does hold documents (e.g. stats not yet updated
+    /// after an import)
+    pub async fn get_display_count(&self, database: &str, collection: &str) -> Result<Option<u64>> {
+        let count = self.get_collection_count(database, collection).await?;
+        if count > 0 {
+            return Ok(Some(count));
+        }
+        let coll = self
+            .client
+            .database(database)
+            .collection::<Document>(collection);
+        if coll.find_one(doc! {}).await?.is_some() {
+            warn!(
+                "Estimated count for '{}.{}' is 0 but it holds documents; stats may be stale",
+                database, collection
+            );
+            return Ok(None);
+        }
+        Ok(Some(0))
+    }
 }
 
-pub async fn copy_collection(
+impl MongoConnection {
+    /// [`get_display_count`](Self::get_display_count) for each of
+    /// `collections`, several at a time, in the same order
+    pub async fn get_display_counts(
+        &self,
+        database: &str,
+        collections: &[String],
+    ) -> Vec<Result<Option<u64>>> {
+        stream::iter(collections)
+            .map(|collection| self.get_display_count(database, collection))
+            .buffered(COUNT_CONCURRENCY)
+            .collect()
+            .await
+    }
+}
+
+/// Describe a count from [`MongoConnection::get_display_count`]
+pub fn format_count(count: Option<u64>) -> String {
+    match count {
+        Some(count) => count.to_string(),
+        None => "unknown (stats unavailable)".to_string(),
+    }
+}
+
+/// Compare document counts, index counts and average document size between
+/// the source and destination collections of `options`
+pub async fn compare_collections(
     source: &MongoConnection,
     dest: &MongoConnection,
-    source_db: &str,
-    source_coll: &str,
-    dest_db: &str,
-    dest_coll: &str,
-    limit: Option<u64>,
-) -> Result<u64> {
+    options: &CopyOptions,
+) -> Result<CollectionComparison> {
     debug!(
-        "Starting collection copy: '{}.{}' -> '{}.{}' (limit: {:?})",
-        source_db, source_coll, dest_db, dest_coll, limit
+        "Comparing '{}.{}' with '{}.{}'",
+        options.source_db, options.source_coll, options.dest_db, options.dest_coll
     );
+    let source_stats = source
+        .collection_stats(&options.source_db, &options.source_coll)
+        .await?;
+    let dest_stats = dest
+        .collection_stats(&options.dest_db, &options.dest_coll)
+        .await?;
 
-    let source_collection = source
-        .get_database(source_db)
-        .collection::<Document>(source_coll);
+    Ok(CollectionComparison {
+        source: source_stats,
+        dest: dest_stats,
+    })
+}
+
+/// Rank `collections` of `database` by data size (from `collStats`) and keep
+/// the slice asked for, in ranked order, logging what was left out
+pub async fn rank_collections(
+    conn: &MongoConnection,
+    database: &str,
+    collections: &[String],
+    slice: CollectionSlice,
+) -> Result<Vec<String>> {
+    let mut sized = Vec::new();
+    for collection in collections {
+        // Views have no collStats, and no size of their own to rank by
+        let size = match conn.collection_stats(database, collection).await {
+            Ok(stats) => stats.size,
+            Err(e) => {
+                debug!("No size for '{}.{}': {:#}", database, collection, e);
+                0
+            }
+        };
+        sized.push((collection.clone(), size));
+    }
 
-    let dest_collection = dest.get_database(dest_db).collection::<Document>(dest_coll);
+    let n = match slice {
+        CollectionSlice::Largest(n) => {
+            sized.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+            n
+        }
+        CollectionSlice::Smallest(n) => {
+            sized.sort_by_key(|(_, size)| *size);
+            n
+        }
+    };
+    let skipped = sized.split_off(n.min(sized.len()));
+
+    for (collection, size) in &sized {
+        info!("Chose '{}' ({})", collection, format_bytes(*size));
+    }
+    for (collection, size) in &skipped {
+        info!("Skipping '{}' ({})", collection, format_bytes(*size));
+    }
+    Ok(sized
+        .into_iter()
+        .map(|(collection, _)| collection)
+        .collect())
+}
+
+/// `percent` of `count` documents, rounded up and at least 1
+pub fn percent_limit(count: u64, percent: f64) -> u64 {
+    // A limit of 0 would mean no limit at all
+    ((count as f64 * percent / 100.0).ceil() as u64).max(1)
+}
 
+/// `options` with [`CopyOptions::limit_percent`] turned into a `limit` from
+/// the source collection's estimated document count
+pub async fn resolve_limit_percent(
+    source: &MongoConnection,
+    options: &CopyOptions,
+) -> Result<CopyOptions> {
+    let percent = match options.limit_percent {
+        Some(percent) if options.limit.is_none() => percent,
+        _ => return Ok(options.clone()),
+    };
+    let count = source
+        .get_collection_count(&options.source_db, &options.source_coll)
+        .await?;
+    let limit = percent_limit(count, percent);
+    if count == 0 {
+        warn!(
+            "Estimated count of '{}.{}' is 0, copying at most 1 document",
+            options.source_db, options.source_coll
+        );
+    }
+    info!(
+        "Copying {}% of '{}.{}': {} of about {} documents",
+        percent, options.source_db, options.source_coll, limit, count
+    );
+    Ok(CopyOptions {
+        limit: Some(limit),
+        limit_percent: None,
+        ..options.clone()
+    })
+}
+
+/// Run the source find for `options`
+pub(crate) async fn open_source_cursor(
+    source_collection: &Collection<Document>,
+    options: &CopyOptions,
+) -> Result<Cursor<Document>> {
     debug!("Creating cursor for source collection");
-    let mut cursor = if let Some(limit_val) = limit {
+    check_exclusions(options)?;
+    if options.match_stage.is_some() {
+        return Ok(source_aggregate(source_collection, options).await?);
+    }
+    let filter = options.effective_filter();
+    debug!("Using find filter: {}", filter);
+    Ok(source_find(source_collection.find(filter), options).await?)
+}
+
+/// The first `count` source documents `options` would copy, filtered and
+/// projected the same way
+pub async fn sample_documents(
+    source: &MongoConnection,
+    options: &CopyOptions,
+    count: u64,
+) -> Result<Vec<Document>> {
+    debug!(
+        "Sampling {} documents from '{}.{}'",
+        count, options.source_db, options.source_coll
+    );
+    let sample = CopyOptions {
+        limit: Some(options.limit.map_or(count, |limit| limit.min(count))),
+        cursor_batch_size: None,
+        no_cursor_timeout: false,
+        ..options.clone()
+    };
+    let collection = source
+        .get_database(&options.source_db)
+        .collection::<Document>(&options.source_coll);
+    let cursor = open_source_cursor(&collection, &sample).await?;
+    Ok(cursor.try_collect().await?)
+}
+
+/// [`open_source_cursor`] reading inside `session`
+async fn open_source_session_cursor(
+    source_collection: &Collection<Document>,
+    options: &CopyOptions,
+    session: &mut ClientSession,
+) -> Result<SessionCursor<Document>> {
+    debug!("Creating snapshot cursor for source collection");
+    check_exclusions(options)?;
+    if options.match_stage.is_some() {
+        return Ok(source_aggregate(source_collection, options)
+            .session(session)
+            .await?);
+    }
+    let filter = options.effective_filter();
+    debug!("Using find filter: {}", filter);
+    Ok(source_find(source_collection.find(filter), options)
+        .session(session)
+        .await?)
+}
+
+/// Source documents sorted by `_id`, reopening the find after the last `_id`
+/// read whenever the server has killed the cursor
+fn resuming_source_stream<'a>(
+    source_collection: &'a Collection<Document>,
+    options: &'a CopyOptions,
+) -> impl TryStream<Ok = Document, Error = mongodb::error::Error> + Unpin + 'a {
+    struct State {
+        cursor: Option<Cursor<Document>>,
+        last_id: Option<Bson>,
+        read: u64,
+        /// Resumes since a document was last read
+        resumes: u32,
+        done: bool,
+    }
+    let state = State {
+        cursor: None,
+        last_id: None,
+        read: 0,
+        resumes: 0,
+        done: false,
+    };
+
+    Box::pin(stream::unfold(state, move |mut state| async move {
+        if state.done {
+            return None;
+        }
+        loop {
+            let cursor = match &mut state.cursor {
+                Some(cursor) => cursor,
+                None => {
+                    let remaining = options.limit.map(|limit| limit - state.read);
+                    if remaining == Some(0) {
+                        return None;
+                    }
+                    let mut filter = options.effective_filter();
+                    if let Some(id) = &state.last_id {
+                        let after = doc! { "_id": { "$gt": id.clone() } };
+                        filter = if filter.is_empty() {
+                            after
+                        } else {
+                            doc! { "$and": [filter, after] }
+                        };
+                    }
+                    debug!("Opening source cursor in _id order with filter {}", filter);
+                    let resumed = CopyOptions {
+                        limit: remaining,
+                        ..options.clone()
+                    };
+                    let find = source_find(source_collection.find(filter), &resumed)
+                        .sort(doc! { "_id": 1 });
+                    match find.await {
+                        Ok(cursor) => state.cursor.insert(cursor),
+                        Err(e) => {
+                            state.done = true;
+                            return Some((Err(e), state));
+                        }
+                    }
+                }
+            };
+            let next = cursor.try_next().await;
+            match next {
+                Ok(Some(doc)) => {
+                    state.last_id = doc.get("_id").cloned();
+                    state.read += 1;
+                    state.resumes = 0;
+                    return Some((Ok(doc), state));
+                }
+                Ok(None) => return None,
+                Err(e) if is_cursor_not_found(&e) && state.resumes < MAX_CURSOR_RESUMES => {
+                    state.resumes += 1;
+                    warn!(
+                        "Source cursor on '{}.{}' was killed after {} documents, reopening it after _id {}",
+                        options.source_db,
+                        options.source_coll,
+                        state.read,
+                        state
+                            .last_id
+                            .as_ref()
+                            .map(ToString::to_string)
+                            .unwrap_or_else(|| "(start)".to_string())
+                    );
+                    state.cursor = None;
+                }
+                Err(e) => {
+                    state.done = true;
+                    return Some((Err(e), state));
+                }
+            }
+        }
+    }))
+}
+
+pub(crate) fn is_capped(spec: &Document) -> bool {
+    spec.get_document("options")
+        .is_ok_and(|options| options.get_bool("capped").unwrap_or(false))
+}
+
+/// `options` for a capped source collection, read in insertion order so the
+/// (ordered) writes reproduce the same sequence
+pub(crate) fn capped_copy_options(options: &CopyOptions) -> CopyOptions {
+    info!(
+        "'{}.{}' is capped, copying its documents in insertion order",
+        options.source_db, options.source_coll
+    );
+    if options.auto_resume_cursor {
+        warn!(
+            "Not resuming killed cursors for capped '{}.{}', that reads in _id order",
+            options.source_db, options.source_coll
+        );
+    }
+    if options.unordered {
+        warn!(
+            "Inserting ordered into capped '{}.{}', unordered inserts could change the sequence",
+            options.source_db, options.source_coll
+        );
+    }
+    CopyOptions {
+        natural_order: true,
+        auto_resume_cursor: false,
+        unordered: false,
+        ..options.clone()
+    }
+}
+
+/// Excluded fields only work with an exclusion projection
+fn check_exclusions(options: &CopyOptions) -> Result<()> {
+    if let Some(projection) = options.effective_projection() {
+        if !options.exclude_fields.is_empty() {
+            validate_projection(&projection)
+                .context("Excluded fields can only be combined with an exclusion projection")?;
+        }
+    }
+    Ok(())
+}
+
+/// Apply the find settings of `options` other than the filter
+fn source_find<'a, S>(
+    mut find: Find<'a, Document, S>,
+    options: &CopyOptions,
+) -> Find<'a, Document, S> {
+    if let Some(limit_val) = options.limit {
         debug!("Applying limit of {} documents", limit_val);
-        source_collection
-            .find(doc! {})
-            .limit(limit_val as i64)
-            .await?
+        find = find.limit(limit_val as i64);
     } else {
         debug!("No limit applied, copying all documents");
-        source_collection.find(doc! {}).await?
+    }
+    if let Some(projection) = options.effective_projection() {
+        debug!("Using projection: {}", projection);
+        find = find.projection(projection);
+    }
+    if let Some(batch_size) = options.cursor_batch_size {
+        debug!("Using cursor batch size of {}", batch_size);
+        find = find.batch_size(batch_size);
+    }
+    if options.natural_order {
+        debug!("Reading in $natural order");
+        find = find.sort(doc! { "$natural": 1 });
+        if options.hint.is_some() {
+            warn!(
+                "Ignoring the hint for '{}.{}', it is read in insertion order",
+                options.source_db, options.source_coll
+            );
+        }
+    } else if let Some(hint) = &options.hint {
+        debug!("Using hint: {:?}", hint);
+        find = find.hint(hint.clone());
+    }
+    if options.no_cursor_timeout {
+        warn!(
+            "Source cursor on '{}.{}' will not time out; it holds server resources until the copy finishes or fails",
+            options.source_db, options.source_coll
+        );
+        find = find.no_cursor_timeout(true);
+    }
+    find
+}
+
+/// The aggregation reading the source when `match_stage` is set, applying
+/// the same filter, order, limit and projection a find would
+fn source_aggregate<'a>(
+    source_collection: &'a Collection<Document>,
+    options: &CopyOptions,
+) -> Aggregate<'a> {
+    let mut pipeline = vec![doc! { "$match": options.effective_filter() }];
+    if let Some(limit) = options.limit {
+        pipeline.push(doc! { "$limit": limit as i64 });
+    }
+    if let Some(projection) = options.effective_projection() {
+        pipeline.push(doc! { "$project": projection });
+    }
+    debug!("Using aggregation pipeline: {:?}", pipeline);
+
+    let mut aggregate = source_collection.aggregate(pipeline);
+    if let Some(batch_size) = options.cursor_batch_size {
+        aggregate = aggregate.batch_size(batch_size);
+    }
+    if options.natural_order {
+        aggregate = aggregate.hint(Hint::Keys(doc! { "$natural": 1 }));
+        if options.hint.is_some() {
+            warn!(
+                "Ignoring the hint for '{}.{}', it is read in insertion order",
+                options.source_db, options.source_coll
+            );
+        }
+    } else if let Some(hint) = &options.hint {
+        aggregate = aggregate.hint(hint.clone());
+    }
+    if options.no_cursor_timeout {
+        warn!("Aggregation cursors can't disable the cursor timeout, ignoring it");
+    }
+    aggregate
+}
+
+/// Start a snapshot session on `conn`, so every read in it sees the same
+/// point in time. Needs a 5.0+ replica set or sharded cluster.
+pub(crate) async fn start_snapshot_session(conn: &MongoConnection) -> Result<ClientSession> {
+    let hello = conn
+        .client
+        .database("admin")
+        .run_command(doc! { "hello": 1 })
+        .await
+        .context("Failed to query source topology")?;
+    let is_replica_set = hello.contains_key("setName");
+    let is_mongos = hello.get_str("msg").is_ok_and(|msg| msg == "isdbgrid");
+    let version = conn.server_version().await?;
+    let major = version
+        .split('.')
+        .next()
+        .and_then(|major| major.parse::<u32>().ok())
+        .unwrap_or(0);
+    if (!is_replica_set && !is_mongos) || major < 5 {
+        anyhow::bail!(
+            "Snapshot reads require the source to be a replica set or sharded cluster running MongoDB 5.0+ (found {} {})",
+            if is_mongos {
+                "sharded cluster"
+            } else if is_replica_set {
+                "replica set"
+            } else {
+                "standalone"
+            },
+            version
+        );
+    }
+
+    debug!("Starting snapshot session on source");
+    conn.client
+        .start_session()
+        .snapshot(true)
+        .await
+        .context("Failed to start snapshot session")
+}
+
+/// Copy one collection as described by `options`.
+///
+/// The copy streams: reading and writing alternate, so at most one write
+/// batch ([`DEFAULT_MAX_BATCH_DOCUMENTS`] or `max_batch_documents` documents,
+/// and at most `max_batch_bytes`, capped at [`MAX_WRITE_BATCH_BYTES`]) is
+/// held at a time, next to the cursor's current server batch
+/// (`cursor_batch_size` documents, at most 16MB). The next document is only
+/// read once a full batch has been written, so a slow destination slows the
+/// reads rather than letting documents pile up.
+pub async fn copy_collection(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    options: &CopyOptions,
+) -> Result<CopyStats, CopyError> {
+    if options.snapshot {
+        let mut session = start_snapshot_session(source).await?;
+        Ok(copy_collection_in(source, dest, options, Some(&mut session)).await?)
+    } else {
+        Ok(copy_collection_in(source, dest, options, None).await?)
+    }
+}
+
+/// [`copy_collection`], reading the source inside `source_session` when given
+async fn copy_collection_in(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    options: &CopyOptions,
+    source_session: Option<&mut ClientSession>,
+) -> Result<CopyStats> {
+    debug!(
+        "Starting collection copy: '{}.{}' -> '{}.{}' (limit: {:?}, filter: {:?}, strategy: {:?})",
+        options.source_db,
+        options.source_coll,
+        options.dest_db,
+        options.dest_coll,
+        options.limit,
+        options.filter,
+        options.write_strategy
+    );
+    check_namespace_length(&options.dest_db, &options.dest_coll)?;
+    let options = &resolve_limit_percent(source, options).await?;
+
+    let source_collection = source
+        .get_database(&options.source_db)
+        .collection::<Document>(&options.source_coll);
+
+    let dest_collection = dest
+        .get_database(&options.dest_db)
+        .collection::<Document>(&options.dest_coll);
+    if options.bypass_validation {
+        warn!(
+            "Bypassing document validation on '{}.{}'",
+            options.dest_db, options.dest_coll
+        );
+    }
+
+    let source_spec = source
+        .collection_spec(&options.source_db, &options.source_coll)
+        .await?;
+    let capped_options;
+    let options = match source_spec.as_ref().filter(|spec| is_capped(spec)) {
+        Some(_) if !options.natural_order => {
+            capped_options = capped_copy_options(options);
+            &capped_options
+        }
+        _ => options,
     };
 
+    check_destination_not_view(dest, options).await?;
+    match &options.dest_collection_options {
+        Some(create_options) => create_destination(dest, options, create_options).await?,
+        None => copy_collection_options(source, dest, options).await?,
+    }
+
+    if let Some(key) = &options.shard_key {
+        shard_collection(dest, &options.dest_db, &options.dest_coll, key).await?;
+    }
+
+    if let Some(ttl) = &options.ttl {
+        ensure_ttl_index(&dest_collection, ttl).await?;
+    }
+
+    if options.write_strategy == WriteStrategy::Upsert && !options.upsert_key.is_empty() {
+        warn_unindexed_upsert_key(&dest_collection, &options.upsert_key).await?;
+    }
+
+    match source_session {
+        Some(session) => {
+            let mut cursor =
+                open_source_session_cursor(&source_collection, options, session).await?;
+            let mut stream = cursor.stream(session);
+            write_source_documents(
+                &mut stream,
+                &source_collection,
+                dest,
+                &dest_collection,
+                options,
+            )
+            .await
+        }
+        // The resuming reads are finds, so an aggregation isn't resumed
+        None if options.auto_resume_cursor && options.match_stage.is_none() => {
+            check_exclusions(options)?;
+            let mut stream = resuming_source_stream(&source_collection, options);
+            write_source_documents(
+                &mut stream,
+                &source_collection,
+                dest,
+                &dest_collection,
+                options,
+            )
+            .await
+        }
+        None => {
+            let mut cursor = open_source_cursor(&source_collection, options).await?;
+            write_source_documents(
+                &mut cursor,
+                &source_collection,
+                dest,
+                &dest_collection,
+                options,
+            )
+            .await
+        }
+    }
+}
+
+/// Copy `cursor` into `dest_collection`, inside a destination transaction
+/// when `options` asks for one
+async fn write_source_documents<S>(
+    cursor: &mut S,
+    source_collection: &Collection<Document>,
+    dest: &MongoConnection,
+    dest_collection: &Collection<Document>,
+    options: &CopyOptions,
+) -> Result<CopyStats>
+where
+    S: TryStream<Ok = Document> + Unpin,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    if !options.transactional {
+        return copy_documents(cursor, dest_collection, options, None).await;
+    }
+
+    ensure_transaction_support(dest).await?;
+    let expected = source_collection
+        .count_documents(options.effective_filter())
+        .await?
+        .min(options.limit.unwrap_or(u64::MAX));
+    if expected > MAX_TRANSACTION_DOCUMENTS {
+        anyhow::bail!(
+            "Transactional copy is limited to {} documents, but '{}.{}' has {}",
+            MAX_TRANSACTION_DOCUMENTS,
+            options.source_db,
+            options.source_coll,
+            expected
+        );
+    }
+
+    debug!("Starting destination transaction");
+    let mut session = dest.client.start_session().await?;
+    session.start_transaction().await?;
+
+    match copy_documents(cursor, dest_collection, options, Some(&mut session)).await {
+        Ok(stats) => {
+            session
+                .commit_transaction()
+                .await
+                .context("Failed to commit transaction")?;
+            debug!("Transaction committed");
+            Ok(stats)
+        }
+        Err(e) => {
+            warn!("Aborting transaction, no documents were committed");
+            if let Err(abort_err) = session.abort_transaction().await {
+                warn!("Failed to abort transaction: {}", abort_err);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Drain `cursor` (a find cursor, or any stream of documents) into
+/// `dest_collection` in batches, inside `session`'s transaction when one is
+/// given. A batch is written, and awaited, once it holds
+/// `max_batch_documents` documents or adding the next document would take its
+/// serialized size past the batch byte limit. So at most one batch, plus the
+/// document that made it full, is ever buffered, however large the source.
+/// Each document waits on the rate limiter first when throttling. Unless
+/// inside a transaction, an error carries a [`PartialCopy`] with what was
+/// written
+pub(crate) async fn copy_documents<S>(
+    cursor: &mut S,
+    dest_collection: &Collection<Document>,
+    options: &CopyOptions,
+    session: Option<&mut ClientSession>,
+) -> Result<CopyStats>
+where
+    S: TryStream<Ok = Document> + Unpin,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    // A failed transaction is rolled back, so nothing counts as written
+    let transactional = session.is_some();
+    let mut writer = DestinationWriter {
+        collection: dest_collection,
+        options,
+        session,
+    };
+    copy_documents_with(cursor, &mut writer, options, transactional).await
+}
+
+/// Where [`copy_documents`] writes its batches
+trait BatchWriter {
+    /// Write all of `batch`; `count` is the running total of documents read,
+    /// for the error log
+    fn write<'a>(&'a mut self, batch: &'a [Document], count: u64) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Writes batches to a destination collection, inside `session` when given
+struct DestinationWriter<'c, 's> {
+    collection: &'c Collection<Document>,
+    options: &'c CopyOptions,
+    session: Option<&'s mut ClientSession>,
+}
+
+impl BatchWriter for DestinationWriter<'_, '_> {
+    fn write<'a>(&'a mut self, batch: &'a [Document], count: u64) -> BoxFuture<'a, Result<()>> {
+        Box::pin(flush_batch(
+            self.collection,
+            batch,
+            self.options,
+            self.session.as_deref_mut(),
+            count,
+        ))
+    }
+}
+
+/// [`copy_documents`] into any [`BatchWriter`]
+async fn copy_documents_with<S, W>(
+    cursor: &mut S,
+    writer: &mut W,
+    options: &CopyOptions,
+    transactional: bool,
+) -> Result<CopyStats>
+where
+    S: TryStream<Ok = Document> + Unpin,
+    S::Error: std::error::Error + Send + Sync + 'static,
+    W: BatchWriter,
+{
+    let mut written = CopyStats::default();
+    copy_documents_into(cursor, writer, options, transactional, &mut written)
+        .await
+        .map_err(|e| {
+            #[cfg(feature = "metrics")]
+            crate::metrics::record_error();
+            if transactional || written.documents == 0 {
+                e
+            } else {
+                e.context(PartialCopy(written))
+            }
+        })
+}
+
+async fn copy_documents_into<S, W>(
+    cursor: &mut S,
+    writer: &mut W,
+    options: &CopyOptions,
+    transactional: bool,
+    written: &mut CopyStats,
+) -> Result<CopyStats>
+where
+    S: TryStream<Ok = Document> + Unpin,
+    S::Error: std::error::Error + Send + Sync + 'static,
+    W: BatchWriter,
+{
+    let max_batch_bytes = batch_byte_limit(options.max_batch_bytes);
+    let max_batch_documents = options
+        .max_batch_documents
+        .unwrap_or(DEFAULT_MAX_BATCH_DOCUMENTS)
+        .max(1);
+    let mut limiter = options.max_docs_per_second.map(|rate| {
+        debug!("Throttling to {} documents per second", rate);
+        RateLimiter::new(rate)
+    });
+
     let mut count = 0u64;
+    let mut bytes = 0u64;
+    let mut skipped = 0u64;
+    let mut skipped_report = None;
     let mut batch = Vec::new();
-    const BATCH_SIZE: usize = 1000;
-    debug!("Using batch size of {} documents", BATCH_SIZE);
+    let mut batch_bytes = 0u64;
+    debug!(
+        "Using batch size of {} documents or {}",
+        max_batch_documents,
+        format_bytes(max_batch_bytes)
+    );
+
+    if !options.set_fields.is_empty() {
+        debug!("Setting fields on every document: {}", options.set_fields);
+    }
+
+    while let Some(mut doc) = cursor.try_next().await? {
+        if let Some(limiter) = limiter.as_mut() {
+            limiter.acquire().await;
+        }
+        set_fields(&mut doc, options);
+        let size = mongodb::bson::to_vec(&doc)?.len() as u64;
+
+        if let Some(max) = options.max_document_bytes.filter(|max| size > *max) {
+            let id = doc.get("_id").map(ToString::to_string).unwrap_or_default();
+            match options.oversized_policy {
+                OversizedPolicy::Skip => {
+                    warn!(
+                        "Skipping document _id {} of {}, over the {} limit",
+                        id,
+                        format_bytes(size),
+                        format_bytes(max)
+                    );
+                    if let Some(path) = &options.skipped_report {
+                        let entry = serde_json::json!({
+                            "namespace": format!("{}.{}", options.source_db, options.source_coll),
+                            "_id": doc.get("_id").cloned().map(Bson::into_relaxed_extjson),
+                            "reason": "oversized",
+                            "bytes": size,
+                            "limit": max,
+                        });
+                        report_skipped(&mut skipped_report, path, &entry)?;
+                    }
+                    skipped += 1;
+                    continue;
+                }
+                OversizedPolicy::Fail => anyhow::bail!(
+                    "Document _id {} is {}, over the {} limit, after {} documents",
+                    id,
+                    format_bytes(size),
+                    format_bytes(max),
+                    count
+                ),
+            }
+        }
+
+        if !batch.is_empty() && batch_bytes + size > max_batch_bytes {
+            debug!(
+                "Batch would exceed {}, flushing {} documents early",
+                format_bytes(max_batch_bytes),
+                batch.len()
+            );
+            writer.write(&batch, count).await?;
+            batch.clear();
+            mark_written(
+                written,
+                CopyStats {
+                    documents: count,
+                    bytes,
+                    skipped,
+                },
+            );
+            batch_bytes = 0;
+            log_progress(count, bytes, limiter.as_ref());
+            if options.stop_requested() {
+                warn!("Stopping after {} documents", count);
+                return Err(Stopped.into());
+            }
+        }
 
-    while let Some(doc) = cursor.try_next().await? {
+        bytes += size;
+        batch_bytes += size;
         batch.push(doc);
         count += 1;
+        // The one-batch bound: a lone document may exceed the byte limit
+        debug_assert!(
+            batch.len() <= max_batch_documents
+                && (batch.len() == 1 || batch_bytes <= max_batch_bytes),
+            "write batch grew past its bound"
+        );
 
-        if batch.len() >= BATCH_SIZE {
-            debug!("Inserting batch of {} documents", batch.len());
-            match dest_collection.insert_many(&batch).await {
-                Ok(_) => {
-                    info!("  Copied {} documents...", count);
-                    batch.clear();
-                }
-                Err(e) => {
-                    error!("Failed to insert batch at document {}: {}", count, e);
-                    return Err(e.into());
-                }
+        if transactional && bytes > MAX_TRANSACTION_BYTES {
+            anyhow::bail!(
+                "Transactional copy is limited to {}, exceeded after {} documents",
+                format_bytes(MAX_TRANSACTION_BYTES),
+                count
+            );
+        }
+
+        if batch.len() >= max_batch_documents {
+            writer.write(&batch, count).await?;
+            batch.clear();
+            mark_written(
+                written,
+                CopyStats {
+                    documents: count,
+                    bytes,
+                    skipped,
+                },
+            );
+            batch_bytes = 0;
+            log_progress(count, bytes, limiter.as_ref());
+            if options.stop_requested() {
+                warn!("Stopping after {} documents", count);
+                return Err(Stopped.into());
             }
         }
     }
 
     if !batch.is_empty() {
-        debug!("Inserting final batch of {} documents", batch.len());
-        match dest_collection.insert_many(&batch).await {
-            Ok(_) => {
-                debug!("Final batch inserted successfully");
+        debug!("Inserting final batch");
+        writer.write(&batch, count).await?;
+        batch.clear();
+        mark_written(
+            written,
+            CopyStats {
+                documents: count,
+                bytes,
+                skipped,
+            },
+        );
+        debug!("Final batch inserted successfully");
+    }
+
+    debug!(
+        "Collection copy completed: {} total documents, {} bytes",
+        count, bytes
+    );
+    if skipped > 0 {
+        warn!("Skipped {} oversized document(s)", skipped);
+    }
+    Ok(CopyStats {
+        documents: count,
+        bytes,
+        skipped,
+    })
+}
+
+/// Merge `set_fields` into `doc`, keeping fields it already has unless
+/// `overwrite_set_fields`
+fn set_fields(doc: &mut Document, options: &CopyOptions) {
+    for (key, value) in &options.set_fields {
+        if options.overwrite_set_fields || !doc.contains_key(key) {
+            doc.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// The byte size at which batches are flushed, never above what the server
+/// accepts in a single write
+fn batch_byte_limit(requested: Option<u64>) -> u64 {
+    match requested {
+        Some(bytes) if bytes > MAX_WRITE_BATCH_BYTES => {
+            warn!(
+                "Batch size of {} exceeds the server's message limit, using {}",
+                format_bytes(bytes),
+                format_bytes(MAX_WRITE_BATCH_BYTES)
+            );
+            MAX_WRITE_BATCH_BYTES
+        }
+        Some(bytes) => bytes,
+        None => DEFAULT_MAX_BATCH_BYTES,
+    }
+}
+
+/// Append `entry` to the skipped-document report at `path`, opening it on
+/// first use
+fn report_skipped(
+    file: &mut Option<std::fs::File>,
+    path: &Path,
+    entry: &serde_json::Value,
+) -> Result<()> {
+    if file.is_none() {
+        debug!("Writing skipped documents to: {:?}", path);
+        let opened = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open skipped report {:?}", path))?;
+        *file = Some(opened);
+    }
+    if let Some(file) = file {
+        writeln!(file, "{}", entry)
+            .with_context(|| format!("Failed to write skipped report {:?}", path))?;
+    }
+    Ok(())
+}
+
+/// Move `written` on to `now` after a batch is flushed, counting the
+/// difference in the copy metrics
+fn mark_written(written: &mut CopyStats, now: CopyStats) {
+    #[cfg(feature = "metrics")]
+    crate::metrics::record_written(now.documents - written.documents, now.bytes - written.bytes);
+    *written = now;
+}
+
+fn log_progress(count: u64, bytes: u64, limiter: Option<&RateLimiter>) {
+    match limiter {
+        Some(limiter) => info!(
+            "  Copied {} documents ({}) at {:.0} docs/s...",
+            count,
+            format_bytes(bytes),
+            limiter.effective_rate()
+        ),
+        None => info!("  Copied {} documents ({})...", count, format_bytes(bytes)),
+    }
+}
+
+/// Write `batch`; `count` is the running total, for the error log
+async fn flush_batch(
+    dest_collection: &Collection<Document>,
+    batch: &[Document],
+    options: &CopyOptions,
+    session: Option<&mut ClientSession>,
+    count: u64,
+) -> Result<()> {
+    debug!("Inserting batch of {} documents", batch.len());
+    match write_batch(
+        dest_collection,
+        batch,
+        options.write_strategy,
+        &options.upsert_key,
+        options.bypass_validation,
+        options.unordered,
+        session,
+    )
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) if is_out_of_space(&e) => {
+            let copied = count - batch.len() as u64;
+            error!(
+                "Destination appears to be out of space after copying {} documents",
+                copied
+            );
+            Err(anyhow::Error::new(e).context(format!(
+                "Destination appears to be out of space after copying {} documents; free up space or raise the quota before retrying",
+                copied
+            )))
+        }
+        Err(e) => {
+            error!("Failed to insert batch at document {}: {}", count, e);
+            Err(e.into())
+        }
+    }
+}
+
+/// Create `ttl`'s index on `collection`; a no-op if the same index exists
+pub(crate) async fn ensure_ttl_index(
+    collection: &Collection<Document>,
+    ttl: &TtlIndex,
+) -> Result<()> {
+    info!(
+        "Ensuring TTL index on '{}.{}' ({} expires after {}s)",
+        collection.namespace().db,
+        collection.name(),
+        ttl.field,
+        ttl.expire_after.as_secs()
+    );
+    let index = IndexModel::builder()
+        .keys(doc! { &ttl.field: 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(ttl.expire_after)
+                .build(),
+        )
+        .build();
+    collection.create_index(index).await.with_context(|| {
+        format!(
+            "Failed to create TTL index on '{}' (an index on that field with other options may already exist)",
+            ttl.field
+        )
+    })?;
+    Ok(())
+}
+
+/// Warn when no destination index starts with the upsert key fields, since
+/// every upsert would then scan the collection
+async fn warn_unindexed_upsert_key(
+    collection: &Collection<Document>,
+    key: &[String],
+) -> Result<()> {
+    let indexes: Vec<IndexModel> = match collection.list_indexes().await {
+        Ok(cursor) => cursor.try_collect().await?,
+        Err(e) if is_namespace_not_found(&e) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let indexed = indexes.iter().any(|index| {
+        let prefix: HashSet<&str> = index
+            .keys
+            .keys()
+            .take(key.len())
+            .map(String::as_str)
+            .collect();
+        prefix.len() == key.len() && key.iter().all(|field| prefix.contains(field.as_str()))
+    });
+    if !indexed {
+        warn!(
+            "No index on '{}.{}' covers the upsert key ({}); each upsert will scan the collection",
+            collection.namespace().db,
+            collection.name(),
+            key.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Documents can't be written into a view, which the server only reports
+/// once the first batch fails
+pub(crate) async fn check_destination_not_view(
+    dest: &MongoConnection,
+    options: &CopyOptions,
+) -> Result<()> {
+    if dest.is_view(&options.dest_db, &options.dest_coll).await? {
+        return Err(CopyError::DestinationIsView(anyhow::anyhow!(
+            "Destination '{}.{}' is a view, not a collection",
+            options.dest_db,
+            options.dest_coll
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Create the destination with the given `create` options instead of the
+/// source collection's; an existing destination is left as it is
+pub(crate) async fn create_destination(
+    dest: &MongoConnection,
+    options: &CopyOptions,
+    create_options: &Document,
+) -> Result<()> {
+    if dest
+        .collection_exists(&options.dest_db, &options.dest_coll)
+        .await?
+    {
+        warn!(
+            "'{}.{}' already exists, so the destination collection options are not applied",
+            options.dest_db, options.dest_coll
+        );
+        return Ok(());
+    }
+
+    info!(
+        "Creating '{}.{}' with options {}",
+        options.dest_db, options.dest_coll, create_options
+    );
+    let mut command = doc! { "create": &options.dest_coll };
+    command.extend(create_options.clone());
+    dest.get_database(&options.dest_db)
+        .run_command(command)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to create '{}.{}' with the destination collection options",
+                options.dest_db, options.dest_coll
+            )
+        })?;
+    Ok(())
+}
+
+/// Collection options that can only be set when the destination is created
+const CREATE_ONLY_OPTIONS: &[&str] = &[
+    "capped",
+    "size",
+    "max",
+    "collation",
+    "clusteredIndex",
+    "expireAfterSeconds",
+];
+/// Collection options that `collMod` can also apply to an existing collection
+const MODIFIABLE_OPTIONS: &[&str] = &["changeStreamPreAndPostImages"];
+
+/// Copy the source collection's options that auto-creation on first insert
+/// would drop: pre-create the destination with them, or apply what `collMod`
+/// can to an existing one. Options that can't be replicated are logged.
+async fn copy_collection_options(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    options: &CopyOptions,
+) -> Result<()> {
+    let source_options = source
+        .collection_spec(&options.source_db, &options.source_coll)
+        .await?
+        .and_then(|spec| spec.get_document("options").ok().cloned())
+        .unwrap_or_default();
+
+    let (mut create_only, modifiable, unsupported) = classify_collection_options(source_options);
+    if !unsupported.is_empty() {
+        warn!(
+            "Not copying option(s) {} of '{}.{}'",
+            unsupported.join(", "),
+            options.source_db,
+            options.source_coll
+        );
+    }
+    if create_only.is_empty() && modifiable.is_empty() {
+        return Ok(());
+    }
+
+    // Clustering can't be added after creation, and documents inserted into
+    // an ordinary collection would silently lose it, so this one must apply
+    let clustered = create_only
+        .get_document("clusteredIndex")
+        .ok()
+        .map(clustered_index_spec);
+    if let Some(spec) = &clustered {
+        create_only.insert("clusteredIndex", spec.clone());
+    }
+
+    let db = dest.get_database(&options.dest_db);
+    if let Some(dest_spec) = dest
+        .collection_spec(&options.dest_db, &options.dest_coll)
+        .await?
+    {
+        if let Some(spec) = &clustered {
+            check_clustered_destination(spec, &dest_spec, options)?;
+            create_only.remove("clusteredIndex");
+            create_only.remove("expireAfterSeconds");
+        }
+        if !create_only.is_empty() {
+            warn!(
+                "Source '{}.{}' has {}, but '{}.{}' already exists; these can only be set at creation, keeping its own",
+                options.source_db, options.source_coll, create_only, options.dest_db, options.dest_coll
+            );
+        }
+        if !modifiable.is_empty() {
+            info!(
+                "Applying {} to '{}.{}'",
+                modifiable, options.dest_db, options.dest_coll
+            );
+            let mut command = doc! { "collMod": &options.dest_coll };
+            command.extend(modifiable);
+            if let Err(e) = db.run_command(command).await {
+                warn!(
+                    "Could not apply the source options to '{}.{}': {}",
+                    options.dest_db, options.dest_coll, e
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    create_only.extend(modifiable);
+    info!(
+        "Creating '{}.{}' with the source options {}",
+        options.dest_db, options.dest_coll, create_only
+    );
+    let command = create_command(&options.dest_coll, create_only);
+    if let Err(e) = db.run_command(command).await {
+        if clustered.is_some() {
+            return Err(e).with_context(|| {
+                format!(
+                    "Failed to create '{}.{}' as a clustered collection (requires MongoDB 5.3+)",
+                    options.dest_db, options.dest_coll
+                )
+            });
+        }
+        warn!(
+            "Could not create '{}.{}' with the source options, it will be created without them: {}",
+            options.dest_db, options.dest_coll, e
+        );
+    }
+    Ok(())
+}
+
+/// Split `source_options` into those that can only be set at creation,
+/// those `collMod` can apply, and the names of the ones not copied
+fn classify_collection_options(source_options: Document) -> (Document, Document, Vec<String>) {
+    let mut create_only = Document::new();
+    let mut modifiable = Document::new();
+    let mut unsupported = Vec::new();
+    for (name, value) in source_options {
+        if CREATE_ONLY_OPTIONS.contains(&name.as_str()) {
+            create_only.insert(name, value);
+        } else if MODIFIABLE_OPTIONS.contains(&name.as_str()) {
+            modifiable.insert(name, value);
+        } else {
+            unsupported.push(name);
+        }
+    }
+    (create_only, modifiable, unsupported)
+}
+
+/// The `create` command for `collection` with `options`
+fn create_command(collection: &str, options: Document) -> Document {
+    let mut command = doc! { "create": collection };
+    command.extend(options);
+    command
+}
+
+/// The `create` form of a `listCollections` clustered index, which also
+/// reports the index version
+fn clustered_index_spec(index: &Document) -> Document {
+    let mut spec = Document::new();
+    for field in ["key", "unique", "name"] {
+        if let Some(value) = index.get(field) {
+            spec.insert(field, value.clone());
+        }
+    }
+    spec
+}
+
+/// An existing destination for a clustered source must be clustered on the
+/// same key, documents written into it would lose the clustering otherwise
+fn check_clustered_destination(
+    spec: &Document,
+    dest_spec: &Document,
+    options: &CopyOptions,
+) -> Result<()> {
+    let dest_key = dest_spec
+        .get_document("options")
+        .and_then(|opts| opts.get_document("clusteredIndex"))
+        .and_then(|index| index.get_document("key"))
+        .ok();
+    if dest_key != spec.get_document("key").ok() {
+        anyhow::bail!(
+            "Source '{}.{}' is a clustered collection, but '{}.{}' already exists without the same clustered index; drop it (or use --on-conflict overwrite) so it can be created clustered",
+            options.source_db,
+            options.source_coll,
+            options.dest_db,
+            options.dest_coll
+        );
+    }
+    Ok(())
+}
+
+/// Enable sharding on the destination database and shard the collection on `key`
+async fn shard_collection(
+    conn: &MongoConnection,
+    database: &str,
+    collection: &str,
+    key: &Document,
+) -> Result<()> {
+    let admin = conn.client.database("admin");
+    let hello = admin
+        .run_command(doc! { "hello": 1 })
+        .await
+        .context("Failed to query destination topology")?;
+    if !hello.get_str("msg").is_ok_and(|msg| msg == "isdbgrid") {
+        anyhow::bail!("--shard-key requires the destination to be a mongos (sharded cluster)");
+    }
+
+    debug!("Enabling sharding on database '{}'", database);
+    admin
+        .run_command(doc! { "enableSharding": database })
+        .await
+        .with_context(|| format!("Failed to enable sharding on database '{}'", database))?;
+
+    let namespace = format!("{}.{}", database, collection);
+    info!("Sharding '{}' on {}", namespace, key);
+    // The shard key index must use simple collation, which the server
+    // insists on being told explicitly when the collection has a default one
+    admin
+        .run_command(doc! {
+            "shardCollection": &namespace,
+            "key": key.clone(),
+            "collation": { "locale": "simple" },
+        })
+        .await
+        .with_context(|| format!("Failed to shard collection '{}'", namespace))?;
+    Ok(())
+}
+
+/// Transactions need a replica set member or a mongos
+async fn ensure_transaction_support(conn: &MongoConnection) -> Result<()> {
+    let hello = conn
+        .client
+        .database("admin")
+        .run_command(doc! { "hello": 1 })
+        .await
+        .context("Failed to query destination topology")?;
+
+    let is_replica_set = hello.contains_key("setName");
+    let is_mongos = hello.get_str("msg").is_ok_and(|msg| msg == "isdbgrid");
+    if !is_replica_set && !is_mongos {
+        anyhow::bail!(
+            "Transactional copy requires the destination to be a replica set or sharded cluster (MongoDB 4.0+)"
+        );
+    }
+    Ok(())
+}
+
+fn is_command_error(e: &mongodb::error::Error) -> bool {
+    matches!(e.kind.as_ref(), mongodb::error::ErrorKind::Command(_))
+}
+
+/// Server error code for bad credentials
+const AUTHENTICATION_FAILED_CODE: i32 = 18;
+
+pub(crate) fn is_authentication_error(e: &mongodb::error::Error) -> bool {
+    match e.kind.as_ref() {
+        mongodb::error::ErrorKind::Authentication { .. } => true,
+        mongodb::error::ErrorKind::Command(c) => c.code == AUTHENTICATION_FAILED_CODE,
+        _ => false,
+    }
+}
+
+/// Whether no server could be reached, or none was usable
+pub(crate) fn is_unreachable(e: &mongodb::error::Error) -> bool {
+    matches!(
+        e.kind.as_ref(),
+        mongodb::error::ErrorKind::DnsResolve { .. }
+            | mongodb::error::ErrorKind::Io(_)
+            | mongodb::error::ErrorKind::ServerSelection { .. }
+    )
+}
+
+/// What most likely went wrong when a connection test failed
+fn connection_failure_hint(e: &mongodb::error::Error) -> Option<&'static str> {
+    use mongodb::error::ErrorKind;
+
+    const AUTHENTICATION: &str = "authentication failed (check username/password/authSource)";
+    const UNREACHABLE: &str =
+        "host unreachable (check the host name, port, firewall and IP access list)";
+    match e.kind.as_ref() {
+        ErrorKind::Authentication { .. } => Some(AUTHENTICATION),
+        ErrorKind::Command(c) if c.code == AUTHENTICATION_FAILED_CODE => Some(AUTHENTICATION),
+        ErrorKind::DnsResolve { .. } | ErrorKind::Io(_) => Some(UNREACHABLE),
+        ErrorKind::ServerSelection { message, .. } => {
+            // The message lists why each server was unusable
+            let message = message.to_lowercase();
+            let unreachable = [
+                "connection refused",
+                "timed out",
+                "failed to lookup",
+                "no such host",
+                "name or service not known",
+                "network is unreachable",
+            ]
+            .iter()
+            .any(|cause| message.contains(cause));
+            if unreachable {
+                Some(UNREACHABLE)
+            } else {
+                Some("no usable server found; the URI likely needs the `directConnection=true` parameter")
             }
+        }
+        _ => None,
+    }
+}
+
+/// Times in a row a killed source cursor is reopened without reading anything
+const MAX_CURSOR_RESUMES: u32 = 5;
+
+fn is_cursor_not_found(e: &mongodb::error::Error) -> bool {
+    matches!(
+        e.kind.as_ref(),
+        mongodb::error::ErrorKind::Command(c) if c.code == 43
+    )
+}
+
+pub(crate) fn is_namespace_not_found(e: &mongodb::error::Error) -> bool {
+    matches!(
+        e.kind.as_ref(),
+        mongodb::error::ErrorKind::Command(c) if c.code == 26
+    )
+}
+
+/// Server error codes reported when the destination has no room left
+const OUT_OF_SPACE_CODES: [i32; 2] = [
+    14031, // OutOfDiskSpace
+    12501, // quota exceeded
+];
+
+/// Server error code for a write violating a unique index
+const DUPLICATE_KEY_CODE: i32 = 11000;
+
+/// Whether a write failed because the destination ran out of disk space or
+/// hit a storage quota (Atlas reports quotas as AtlasError 8000)
+pub(crate) fn is_out_of_space(e: &mongodb::error::Error) -> bool {
+    write_failures(e).into_iter().any(|(code, message)| {
+        OUT_OF_SPACE_CODES.contains(&code)
+            || (code == 8000 && message.contains("space quota"))
+            || message.contains("No space left on device")
+    })
+}
+
+pub(crate) fn is_duplicate_key(e: &mongodb::error::Error) -> bool {
+    write_failures(e)
+        .into_iter()
+        .any(|(code, _)| code == DUPLICATE_KEY_CODE)
+}
+
+/// Code and message of every failure a command or write reported
+fn write_failures(e: &mongodb::error::Error) -> Vec<(i32, &str)> {
+    use mongodb::error::{ErrorKind, WriteFailure};
+
+    let mut failures: Vec<(i32, &str)> = Vec::new();
+    match e.kind.as_ref() {
+        ErrorKind::Command(c) => failures.push((c.code, &c.message)),
+        ErrorKind::Write(WriteFailure::WriteError(w)) => failures.push((w.code, &w.message)),
+        ErrorKind::Write(WriteFailure::WriteConcernError(w)) => failures.push((w.code, &w.message)),
+        ErrorKind::InsertMany(m) => {
+            for w in m.write_errors.iter().flatten() {
+                failures.push((w.code, &w.message));
+            }
+            if let Some(w) = &m.write_concern_error {
+                failures.push((w.code, &w.message));
+            }
+        }
+        _ => {}
+    }
+    failures
+}
+
+/// Read a numeric field that the server may return as any BSON number type
+fn number_field(doc: &Document, key: &str) -> f64 {
+    match doc.get(key) {
+        Some(Bson::Int32(v)) => *v as f64,
+        Some(Bson::Int64(v)) => *v as f64,
+        Some(Bson::Double(v)) => *v,
+        _ => 0.0,
+    }
+}
+
+async fn write_batch(
+    collection: &Collection<Document>,
+    batch: &[Document],
+    strategy: WriteStrategy,
+    upsert_key: &[String],
+    bypass_validation: bool,
+    unordered: bool,
+    mut session: Option<&mut ClientSession>,
+) -> mongodb::error::Result<()> {
+    match strategy {
+        WriteStrategy::Insert => {
+            let insert = collection
+                .insert_many(batch)
+                .ordered(!unordered)
+                .bypass_document_validation(bypass_validation);
+            match session {
+                Some(session) => insert.session(session).await?,
+                None => insert.await?,
+            };
+        }
+        WriteStrategy::Upsert if !upsert_key.is_empty() => {
+            for doc in batch {
+                let Some(filter) = upsert_filter(doc, upsert_key) else {
+                    debug!("Document is missing an upsert key field, inserting it");
+                    let insert = collection
+                        .insert_one(doc)
+                        .bypass_document_validation(bypass_validation);
+                    match session.as_deref_mut() {
+                        Some(session) => insert.session(session).await?,
+                        None => insert.await?,
+                    };
+                    continue;
+                };
+                // A matched document keeps its own _id, which can't change
+                let mut fields = doc.clone();
+                let mut update = doc! {};
+                if let Some(id) = fields.remove("_id") {
+                    update.insert("$setOnInsert", doc! { "_id": id });
+                }
+                update.insert("$set", fields);
+                let upsert = collection
+                    .update_one(filter, update)
+                    .upsert(true)
+                    .bypass_document_validation(bypass_validation);
+                match session.as_deref_mut() {
+                    Some(session) => upsert.session(session).await?,
+                    None => upsert.await?,
+                };
+            }
+        }
+        WriteStrategy::MissingOnly => {
+            // One extra read per batch, for the _ids already at the destination
+            let ids: Vec<Bson> = batch
+                .iter()
+                .filter_map(|doc| doc.get("_id").cloned())
+                .collect();
+            let find = collection
+                .find(doc! { "_id": { "$in": ids } })
+                .projection(doc! { "_id": 1 });
+            let existing: Vec<Document> = match session.as_deref_mut() {
+                Some(session) => {
+                    let mut cursor = find.session(&mut *session).await?;
+                    cursor.stream(session).try_collect().await?
+                }
+                None => find.await?.try_collect().await?,
+            };
+            let existing: Vec<&Bson> = existing.iter().filter_map(|doc| doc.get("_id")).collect();
+            let missing: Vec<&Document> = batch
+                .iter()
+                .filter(|doc| !doc.get("_id").is_some_and(|id| existing.contains(&id)))
+                .collect();
+            debug!(
+                "{} of {} documents already at the destination",
+                batch.len() - missing.len(),
+                batch.len()
+            );
+            if !missing.is_empty() {
+                let insert = collection
+                    .insert_many(missing)
+                    .ordered(!unordered)
+                    .bypass_document_validation(bypass_validation);
+                match session {
+                    Some(session) => insert.session(session).await?,
+                    None => insert.await?,
+                };
+            }
+        }
+        WriteStrategy::Upsert => {
+            for doc in batch {
+                let Some(id) = doc.get("_id") else {
+                    let insert = collection
+                        .insert_one(doc)
+                        .bypass_document_validation(bypass_validation);
+                    match session.as_deref_mut() {
+                        Some(session) => insert.session(session).await?,
+                        None => insert.await?,
+                    };
+                    continue;
+                };
+                let replace = collection
+                    .replace_one(doc! { "_id": id.clone() }, doc)
+                    .upsert(true)
+                    .bypass_document_validation(bypass_validation);
+                match session.as_deref_mut() {
+                    Some(session) => replace.session(session).await?,
+                    None => replace.await?,
+                };
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Equality filter on the `key` fields of `doc`, `None` if any is missing
+fn upsert_filter(doc: &Document, key: &[String]) -> Option<Document> {
+    let mut filter = Document::new();
+    for field in key {
+        let mut parts = field.split('.');
+        let mut value = doc.get(parts.next()?)?;
+        for part in parts {
+            value = value.as_document()?.get(part)?;
+        }
+        filter.insert(field.clone(), value.clone());
+    }
+    Some(filter)
+}
+
+/// Copy a GridFS bucket, chunks first so that no file document is visible at
+/// the destination before its data, then verify every copied file arrived.
+/// `template` supplies the per-collection settings as in [`copy_database`];
+/// chunks and files must arrive unchanged, so it should not filter or
+/// transform documents
+pub async fn copy_gridfs_bucket(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    source_db: &str,
+    dest_db: &str,
+    bucket: &str,
+    template: &CopyOptions,
+) -> Result<CopyStats, CopyError> {
+    Ok(try_copy_gridfs_bucket(source, dest, source_db, dest_db, bucket, template).await?)
+}
+
+async fn try_copy_gridfs_bucket(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    source_db: &str,
+    dest_db: &str,
+    bucket: &str,
+    template: &CopyOptions,
+) -> Result<CopyStats> {
+    debug!(
+        "Starting GridFS bucket copy: '{}.{}' -> '{}.{}'",
+        source_db, bucket, dest_db, bucket
+    );
+    let files = format!("{}.files", bucket);
+    let chunks = format!("{}.chunks", bucket);
+
+    // The files this run copies; the destination bucket may hold others
+    let file_ids: Vec<Bson> = source
+        .get_database(source_db)
+        .collection::<Document>(&files)
+        .find(doc! {})
+        .projection(doc! { "_id": 1 })
+        .await?
+        .try_collect::<Vec<Document>>()
+        .await?
+        .into_iter()
+        .filter_map(|doc| doc.get("_id").cloned())
+        .collect();
+
+    let mut totals = CopyStats::default();
+    for coll in [&chunks, &files] {
+        if template.stop_requested() {
+            warn!("Stopping before '{}.{}'", source_db, coll);
+            return Err(anyhow::Error::new(Stopped).context(PartialCopy(totals)));
+        }
+        info!("Copying '{}.{}'", source_db, coll);
+        let options = CopyOptions {
+            source_db: source_db.to_string(),
+            source_coll: coll.clone(),
+            dest_db: dest_db.to_string(),
+            dest_coll: coll.clone(),
+            ..template.clone()
+        };
+        match copy_collection(source, dest, &options).await {
+            Ok(stats) => totals.add(stats),
             Err(e) => {
-                error!("Failed to insert final batch: {}", e);
+                error!("Failed to copy GridFS collection '{}': {}", coll, e);
                 return Err(e.into());
             }
         }
     }
 
-    debug!("Collection copy completed: {} total documents", count);
-    Ok(count)
+    let dest_files = dest.get_database(dest_db).collection::<Document>(&files);
+    let mut found = 0;
+    for ids in file_ids.chunks(DEFAULT_MAX_BATCH_DOCUMENTS) {
+        found += dest_files
+            .count_documents(doc! { "_id": { "$in": ids.to_vec() } })
+            .await?;
+    }
+    if found != file_ids.len() as u64 {
+        anyhow::bail!(
+            "GridFS bucket '{}' verification failed: {} of {} copied file(s) are missing from the destination",
+            bucket,
+            file_ids.len() as u64 - found,
+            file_ids.len()
+        );
+    }
+
+    info!("Verified {} file(s) in bucket '{}'", found, bucket);
+    Ok(totals)
 }
 
+/// Copy every collection of `source_db`, using `template` for the
+/// per-collection settings with its namespace replaced for each collection.
+/// Collections already in `dest_db` are handled by `on_conflict`, or simply
+/// written into when `None`
 pub async fn copy_database(
     source: &MongoConnection,
     dest: &MongoConnection,
     source_db: &str,
     dest_db: &str,
-) -> Result<()> {
-    debug!("Starting database copy: '{}' -> '{}'", source_db, dest_db);
+    template: &CopyOptions,
+    on_conflict: Option<ConflictPolicy>,
+) -> Result<CopyStats, CopyError> {
     let collections = source.list_collections(source_db).await?;
+    copy_collections(
+        source,
+        dest,
+        source_db,
+        dest_db,
+        &collections,
+        template,
+        on_conflict,
+    )
+    .await
+}
 
+/// [`copy_database`] restricted to the given collections of `source_db`
+pub async fn copy_collections(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    source_db: &str,
+    dest_db: &str,
+    collections: &[String],
+    template: &CopyOptions,
+    on_conflict: Option<ConflictPolicy>,
+) -> Result<CopyStats, CopyError> {
+    Ok(try_copy_collections(
+        source,
+        dest,
+        source_db,
+        dest_db,
+        collections,
+        template,
+        on_conflict,
+    )
+    .await?)
+}
+
+async fn try_copy_collections(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    source_db: &str,
+    dest_db: &str,
+    collections: &[String],
+    template: &CopyOptions,
+    on_conflict: Option<ConflictPolicy>,
+) -> Result<CopyStats> {
+    debug!("Starting database copy: '{}' -> '{}'", source_db, dest_db);
     info!("Copying database '{}' to '{}'", source_db, dest_db);
     info!("Found {} collections", collections.len());
+    // Fail before copying anything rather than partway through
+    for collection in collections {
+        check_namespace_length(dest_db, collection)?;
+    }
+
+    // One session for the whole run, so every collection is read at the
+    // same point in time
+    let mut snapshot = if template.snapshot {
+        info!("Reading '{}' from a single snapshot", source_db);
+        Some(start_snapshot_session(source).await?)
+    } else {
+        None
+    };
 
+    let mut progress = match &template.progress_dir {
+        Some(dir) => {
+            let mut state = ResumeState::load(dir, &source.uri, &dest.uri, source_db, dest_db)?;
+            if !template.resume {
+                state.reset()?;
+            } else if !state.completed().is_empty() {
+                info!(
+                    "Resuming '{}': {} collection(s) already copied",
+                    source_db,
+                    state.completed().len()
+                );
+            }
+            Some(state)
+        }
+        None => None,
+    };
+
+    let started = std::time::Instant::now();
+    let mut totals = CopyStats::default();
     for (idx, collection) in collections.iter().enumerate() {
+        if template.resume
+            && progress
+                .as_ref()
+                .is_some_and(|p| p.is_completed(collection))
+        {
+            info!(
+                "'{}.{}' was copied by a previous run, skipping it",
+                source_db, collection
+            );
+            continue;
+        }
+        if template.stop_requested() {
+            warn!(
+                "Stopping before '{}.{}', {} collection(s) not copied",
+                source_db,
+                collection,
+                collections.len() - idx
+            );
+            return Err(anyhow::Error::new(Stopped).context(PartialCopy(totals)));
+        }
         info!(
             "\nCopying collection '{}' ({}/{})",
             collection,
@@ -191,21 +2863,285 @@ pub async fn copy_database(
         );
         debug!("Collection: '{}.{}'", source_db, collection);
 
-        match copy_collection(
-            source, dest, source_db, collection, dest_db, collection, None,
-        )
-        .await
-        {
-            Ok(count) => {
-                info!("Copied {} documents from '{}'", count, collection);
+        let mut options = CopyOptions {
+            source_db: source_db.to_string(),
+            source_coll: collection.clone(),
+            dest_db: dest_db.to_string(),
+            dest_coll: collection.clone(),
+            ..template.clone()
+        };
+        if let Some(policy) = on_conflict {
+            if dest.collection_exists(dest_db, collection).await? {
+                match policy {
+                    ConflictPolicy::Skip => {
+                        info!("'{}.{}' already exists, skipping it", dest_db, collection);
+                        continue;
+                    }
+                    ConflictPolicy::Overwrite => {
+                        info!("Dropping existing '{}.{}'", dest_db, collection);
+                        dest.get_database(dest_db)
+                            .collection::<Document>(collection)
+                            .drop()
+                            .await
+                            .with_context(|| {
+                                format!("Failed to drop '{}.{}'", dest_db, collection)
+                            })?;
+                    }
+                    ConflictPolicy::Merge => {
+                        debug!("Merging into existing '{}.{}'", dest_db, collection);
+                        options.write_strategy = WriteStrategy::Upsert;
+                    }
+                }
+            }
+        }
+        match copy_collection_in(source, dest, &options, snapshot.as_mut()).await {
+            Ok(stats) => {
+                info!(
+                    "Copied {} documents ({}) from '{}'",
+                    stats.documents,
+                    format_bytes(stats.bytes),
+                    collection
+                );
+                totals.add(stats);
+                info!(
+                    "Database progress: {}/{} collections done, {} documents ({}) copied in {:.0?}",
+                    idx + 1,
+                    collections.len(),
+                    totals.documents,
+                    format_bytes(totals.bytes),
+                    started.elapsed()
+                );
+                if let Some(progress) = &mut progress {
+                    progress.mark_completed(collection)?;
+                }
             }
             Err(e) => {
-                error!("Failed to copy collection '{}': {}", collection, e);
+                error!("Failed to copy collection '{}': {:#}", collection, e);
+                if progress.is_some() {
+                    info!(
+                        "Progress saved, a resumed run will start from '{}'",
+                        collection
+                    );
+                }
                 return Err(e);
             }
         }
     }
 
+    if let Some(progress) = &progress {
+        progress.clear()?;
+    }
     debug!("Database copy completed successfully");
-    Ok(())
+    Ok(totals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::{spec::BinarySubtype, Binary};
+    use std::sync::atomic::AtomicU64;
+
+    const MIB: usize = 1024 * 1024;
+
+    /// A destination that takes a while to write each batch, recording
+    /// their sizes
+    struct SlowSink {
+        written: Arc<AtomicU64>,
+        /// (documents, serialized bytes) of each batch written
+        batches: Vec<(usize, u64)>,
+    }
+
+    impl BatchWriter for SlowSink {
+        fn write<'a>(
+            &'a mut self,
+            batch: &'a [Document],
+            _count: u64,
+        ) -> BoxFuture<'a, Result<()>> {
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(2)).await;
+                let bytes = batch.iter().map(bson_size).sum();
+                self.batches.push((batch.len(), bytes));
+                self.written.fetch_add(batch.len() as u64, Ordering::SeqCst);
+                Ok(())
+            })
+        }
+    }
+
+    fn bson_size(doc: &Document) -> u64 {
+        mongodb::bson::to_vec(doc).unwrap().len() as u64
+    }
+
+    fn document_of(id: u64, payload: usize) -> Document {
+        doc! {
+            "_id": id as i64,
+            "payload": Binary { subtype: BinarySubtype::Generic, bytes: vec![0; payload] },
+        }
+    }
+
+    /// Copy `total` generated documents with `payload` bytes each into a
+    /// [`SlowSink`]. Also returns the most documents ever read from the
+    /// source but not yet written, i.e. held in memory.
+    async fn copy_generated(options: &CopyOptions, total: u64, payload: usize) -> (SlowSink, u64) {
+        let read = Arc::new(AtomicU64::new(0));
+        let written = Arc::new(AtomicU64::new(0));
+        let peak = Arc::new(AtomicU64::new(0));
+        let mut source = {
+            let (read, written, peak) = (read.clone(), written.clone(), peak.clone());
+            // Documents are only created when the copy asks for them
+            stream::iter((0..total).map(move |id| {
+                let held = read.fetch_add(1, Ordering::SeqCst) + 1 - written.load(Ordering::SeqCst);
+                peak.fetch_max(held, Ordering::SeqCst);
+                Ok::<_, std::io::Error>(document_of(id, payload))
+            }))
+        };
+        let mut sink = SlowSink {
+            written,
+            batches: Vec::new(),
+        };
+        let stats = copy_documents_with(&mut source, &mut sink, options, false)
+            .await
+            .unwrap();
+        assert_eq!(stats.documents, total);
+        assert_eq!(read.load(Ordering::SeqCst), total);
+        (sink, peak.load(Ordering::SeqCst))
+    }
+
+    /// A clustered index as `listCollections` reports it
+    fn listed_clustered_index() -> Document {
+        doc! { "key": { "_id": 1 }, "unique": true, "name": "by_id", "v": 2 }
+    }
+
+    #[test]
+    fn clustered_index_spec_keeps_only_create_fields() {
+        assert_eq!(
+            clustered_index_spec(&listed_clustered_index()),
+            doc! { "key": { "_id": 1 }, "unique": true, "name": "by_id" }
+        );
+        // name is optional, the server generates one
+        assert_eq!(
+            clustered_index_spec(&doc! { "key": { "_id": 1 }, "unique": true, "v": 2 }),
+            doc! { "key": { "_id": 1 }, "unique": true }
+        );
+    }
+
+    #[test]
+    fn capped_collections_are_created_capped() {
+        let listed = doc! {
+            "capped": true,
+            "size": 1_048_576_i64,
+            "max": 1000,
+            "validator": { "level": { "$exists": true } },
+        };
+        let (create_only, modifiable, unsupported) = classify_collection_options(listed);
+        assert!(modifiable.is_empty());
+        assert_eq!(unsupported, vec!["validator".to_string()]);
+        assert_eq!(
+            create_command("log", create_only),
+            doc! { "create": "log", "capped": true, "size": 1_048_576_i64, "max": 1000 }
+        );
+    }
+
+    #[test]
+    fn existing_destination_must_be_clustered_on_the_same_key() {
+        let options = CopyOptions::new("src", "events", "dst", "events");
+        let spec = clustered_index_spec(&listed_clustered_index());
+
+        let plain = doc! { "name": "events", "type": "collection", "options": {} };
+        let error = check_clustered_destination(&spec, &plain, &options).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("without the same clustered index"));
+
+        let other_key = doc! {
+            "name": "events",
+            "options": { "clusteredIndex": { "key": { "ts": 1 }, "unique": true, "v": 2 } },
+        };
+        assert!(check_clustered_destination(&spec, &other_key, &options).is_err());
+
+        let same_key = doc! {
+            "name": "events",
+            "options": { "clusteredIndex": listed_clustered_index() },
+        };
+        assert!(check_clustered_destination(&spec, &same_key, &options).is_ok());
+    }
+
+    #[test]
+    fn percent_limit_rounds_up_to_at_least_one_document() {
+        assert_eq!(percent_limit(1000, 10.0), 100);
+        assert_eq!(percent_limit(1001, 10.0), 101);
+        assert_eq!(percent_limit(5, 0.5), 1);
+        assert_eq!(percent_limit(0, 50.0), 1);
+        assert_eq!(percent_limit(42, 100.0), 42);
+    }
+
+    #[test]
+    fn batch_byte_limit_is_clamped_to_the_message_limit() {
+        assert_eq!(batch_byte_limit(None), DEFAULT_MAX_BATCH_BYTES);
+        assert_eq!(batch_byte_limit(Some(MIB as u64)), MIB as u64);
+        assert_eq!(
+            batch_byte_limit(Some(MAX_WRITE_BATCH_BYTES)),
+            MAX_WRITE_BATCH_BYTES
+        );
+        assert_eq!(
+            batch_byte_limit(Some(MAX_WRITE_BATCH_BYTES + 1)),
+            MAX_WRITE_BATCH_BYTES
+        );
+        assert_eq!(batch_byte_limit(Some(u64::MAX)), MAX_WRITE_BATCH_BYTES);
+    }
+
+    #[tokio::test]
+    async fn multi_mib_documents_are_split_before_the_batch_limit() {
+        // Asking for more than the server accepts still splits below it
+        let options = CopyOptions::default().with_max_batch_bytes(Some(u64::MAX));
+        let (sink, _) = copy_generated(&options, 30, 7 * MIB).await;
+
+        assert!(sink
+            .batches
+            .iter()
+            .all(|&(_, bytes)| bytes <= MAX_WRITE_BATCH_BYTES));
+        // 6 documents of 7MiB fit in 48MB, a 7th would not
+        assert!(sink.batches.iter().all(|&(documents, _)| documents == 6));
+        assert_eq!(sink.batches.len(), 5);
+
+        let options = CopyOptions::default().with_max_batch_bytes(Some(16 * MIB as u64));
+        let (sink, _) = copy_generated(&options, 9, 5 * MIB).await;
+        let sizes: Vec<usize> = sink
+            .batches
+            .iter()
+            .map(|&(documents, _)| documents)
+            .collect();
+        assert_eq!(sizes, [3, 3, 3]);
+    }
+
+    #[tokio::test]
+    async fn large_documents_buffer_at_most_one_batch() {
+        let max_batch_bytes = 10 * MIB as u64;
+        let options = CopyOptions::default().with_max_batch_bytes(Some(max_batch_bytes));
+        // 160MiB streamed under a 10MiB batch budget
+        let (sink, peak) = copy_generated(&options, 40, 4 * MIB).await;
+
+        assert!(sink
+            .batches
+            .iter()
+            .all(|&(_, bytes)| bytes <= max_batch_bytes));
+        assert!(sink.batches.iter().all(|&(documents, _)| documents == 2));
+        // One full batch plus the document that didn't fit in it
+        assert!(peak <= 3, "{} documents were held at once", peak);
+    }
+
+    #[tokio::test]
+    async fn batch_document_limit_bounds_buffering() {
+        let options = CopyOptions::default().with_max_batch_documents(Some(5));
+        let (sink, peak) = copy_generated(&options, 103, 1024).await;
+
+        let sizes: Vec<usize> = sink
+            .batches
+            .iter()
+            .map(|&(documents, _)| documents)
+            .collect();
+        assert_eq!(sizes.len(), 21);
+        assert!(sizes[..20].iter().all(|&documents| documents == 5));
+        assert_eq!(sizes[20], 3);
+        assert!(peak <= 5, "{} documents were held at once", peak);
+    }
 }
@@ -0,0 +1,186 @@
+use anyhow::{bail, Context, Result};
+use mongodb::bson::DateTime;
+use std::collections::BTreeMap;
+
+/// Databases used internally by MongoDB that must not be copied into
+pub const SYSTEM_DATABASES: [&str; 3] = ["admin", "local", "config"];
+
+/// Longest database name MongoDB accepts, in bytes (names must be shorter
+/// than 64)
+pub const MAX_DATABASE_NAME_BYTES: usize = 63;
+
+/// Longest `database.collection` namespace MongoDB (4.4+) accepts, in bytes
+pub const MAX_NAMESPACE_BYTES: usize = 255;
+
+pub fn is_system_database(name: &str) -> bool {
+    SYSTEM_DATABASES.contains(&name)
+}
+
+/// Fail if `db` is longer than MongoDB allows, suggesting a shorter name
+pub fn check_database_name_length(db: &str) -> Result<()> {
+    if db.len() > MAX_DATABASE_NAME_BYTES {
+        bail!(
+            "database name '{}' is {} bytes, over MongoDB's limit of {}; use a shorter name, e.g. '{}'",
+            db,
+            db.len(),
+            MAX_DATABASE_NAME_BYTES,
+            truncate(db, MAX_DATABASE_NAME_BYTES)
+        );
+    }
+    Ok(())
+}
+
+/// Fail if `db.coll` is longer than MongoDB allows, suggesting a shorter
+/// collection name. The server only rejects it on the first write.
+pub fn check_namespace_length(db: &str, coll: &str) -> Result<()> {
+    check_database_name_length(db)?;
+    let length = db.len() + 1 + coll.len();
+    if length > MAX_NAMESPACE_BYTES {
+        let room = MAX_NAMESPACE_BYTES - db.len() - 1;
+        bail!(
+            "namespace '{}.{}' is {} bytes, over MongoDB's limit of {}; use a collection name of at most {} bytes, e.g. '{}', or a shorter database name",
+            db,
+            coll,
+            length,
+            MAX_NAMESPACE_BYTES,
+            room,
+            truncate(coll, room)
+        );
+    }
+    Ok(())
+}
+
+/// The longest prefix of `name` that fits in `max` bytes
+fn truncate(name: &str, max: usize) -> &str {
+    let mut end = max.min(name.len());
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    &name[..end]
+}
+
+/// Split a `database.collection` namespace into its parts
+pub fn parse_namespace(ns: &str) -> Result<(String, String)> {
+    // Database names cannot contain '.', collection names can
+    match ns.split_once('.') {
+        Some((db, coll)) if !db.is_empty() && !coll.is_empty() => {
+            Ok((db.to_string(), coll.to_string()))
+        }
+        _ => bail!("invalid namespace '{}', expected 'database.collection'", ns),
+    }
+}
+
+/// Groups of names that differ only by case (or not at all), which collide
+/// on case-insensitive targets: database names on one server, or files in a
+/// dump on some filesystems
+pub fn case_collisions<'a, I>(names: I) -> Vec<Vec<&'a str>>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut groups: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for name in names {
+        groups.entry(name.to_lowercase()).or_default().push(name);
+    }
+    groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+/// Match a name against a glob pattern supporting `*` and `?`
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = backtrack {
+            // Let the last '*' swallow one more character
+            p = star_p + 1;
+            n = star_n + 1;
+            backtrack = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Append `date` rendered with `format` to a collection name, e.g. `events`
+/// with `%Y_%m` becomes `events_2024_06`. Supports `%Y`, `%m`, `%d`, `%H`,
+/// `%M`, `%S` and `%%`, all in UTC.
+pub fn dated_collection_name(name: &str, format: &str, date: DateTime) -> Result<String> {
+    let suffix = format_date(format, date)?;
+    if suffix.contains('$') || suffix.contains('\0') {
+        bail!("date suffix '{}' is not valid in a collection name", suffix);
+    }
+    Ok(format!("{}_{}", name, suffix))
+}
+
+fn format_date(format: &str, date: DateTime) -> Result<String> {
+    // e.g. 2024-06-01T12:34:56.789Z
+    let rfc3339 = date
+        .try_to_rfc3339_string()
+        .context("date is out of range")?;
+    let field = |range: std::ops::Range<usize>| &rfc3339[range];
+
+    let mut out = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => out.push_str(field(0..4)),
+            Some('m') => out.push_str(field(5..7)),
+            Some('d') => out.push_str(field(8..10)),
+            Some('H') => out.push_str(field(11..13)),
+            Some('M') => out.push_str(field(14..16)),
+            Some('S') => out.push_str(field(17..19)),
+            Some('%') => out.push('%'),
+            Some(other) => bail!("unsupported date specifier '%{}'", other),
+            None => bail!("date format ends with a lone '%'"),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn database_names_must_be_shorter_than_64_bytes() {
+        assert!(check_database_name_length(&"d".repeat(63)).is_ok());
+        let error = check_database_name_length(&"d".repeat(64)).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains(&format!("e.g. '{}'", "d".repeat(63))));
+    }
+
+    #[test]
+    fn namespaces_are_limited_to_255_bytes() {
+        let db = "d".repeat(10);
+        // 10 + '.' + 244 = 255
+        assert!(check_namespace_length(&db, &"c".repeat(244)).is_ok());
+        let error = check_namespace_length(&db, &"c".repeat(245)).unwrap_err();
+        assert!(error.to_string().contains("at most 244 bytes"));
+        assert!(check_namespace_length(&"d".repeat(64), "c").is_err());
+    }
+
+    #[test]
+    fn suggested_names_end_on_a_character_boundary() {
+        assert_eq!(truncate("ééé", 3), "é");
+        assert_eq!(truncate("abc", 10), "abc");
+    }
+}
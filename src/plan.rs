@@ -0,0 +1,116 @@
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use mongodb::bson::{Bson, Document};
+use serde::Serialize;
+use tracing::debug;
+
+use crate::mongo::{
+    resolve_limit_percent, CopyOptions, MongoConnection, WriteStrategy, COUNT_CONCURRENCY,
+};
+use crate::namespace::check_namespace_length;
+use crate::uri::mask_uri;
+
+/// Every namespace a run will copy, worked out before anything is written so
+/// it can be reviewed (or printed with `--dry-run`) and then executed as-is
+#[derive(Debug, Serialize)]
+pub struct CopyPlan {
+    /// Source URI with credentials masked
+    pub source: String,
+    /// Destination URI with credentials masked
+    pub destination: String,
+    pub entries: Vec<PlanEntry>,
+}
+
+/// One collection copy within a [`CopyPlan`]
+#[derive(Debug, Serialize)]
+pub struct PlanEntry {
+    /// Source namespace (`database.collection`)
+    pub from: String,
+    /// Destination namespace (`database.collection`)
+    pub to: String,
+    /// Estimated source document count, `None` when stats are unavailable
+    pub documents: Option<u64>,
+    /// Whether the destination collection already exists
+    pub destination_exists: bool,
+    pub limit: Option<u64>,
+    /// Find filter as relaxed extended JSON
+    pub filter: Option<serde_json::Value>,
+    /// Aggregation $match stage as relaxed extended JSON
+    #[serde(rename = "match")]
+    pub match_stage: Option<serde_json::Value>,
+    /// Projection as relaxed extended JSON
+    pub projection: Option<serde_json::Value>,
+    pub write: WriteStrategy,
+    /// The options the entry is executed with
+    #[serde(skip)]
+    pub options: CopyOptions,
+}
+
+impl CopyPlan {
+    /// Sum of the known document counts, capped by each entry's limit
+    pub fn total_documents(&self) -> u64 {
+        self.entries
+            .iter()
+            .filter_map(|entry| match (entry.documents, entry.limit) {
+                (Some(count), Some(limit)) => Some(count.min(limit)),
+                (count, _) => count,
+            })
+            .sum()
+    }
+}
+
+/// Look up counts and destination state for each of `options`, without
+/// copying anything
+pub async fn build_plan(
+    source: &MongoConnection,
+    dest: &MongoConnection,
+    options: Vec<CopyOptions>,
+) -> Result<CopyPlan> {
+    debug!("Building copy plan for {} collection(s)", options.len());
+    for options in &options {
+        check_namespace_length(&options.dest_db, &options.dest_coll)?;
+    }
+    // Look up several namespaces at a time, keeping the plan's order
+    let lookups: Vec<Result<(CopyOptions, Option<u64>, bool)>> = stream::iter(&options)
+        .map(|options| async move {
+            let options = resolve_limit_percent(source, options).await?;
+            let documents = source
+                .get_display_count(&options.source_db, &options.source_coll)
+                .await?;
+            let destination_exists = dest
+                .collection_spec(&options.dest_db, &options.dest_coll)
+                .await?
+                .is_some();
+            Ok((options, documents, destination_exists))
+        })
+        .buffered(COUNT_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut entries = Vec::with_capacity(options.len());
+    for lookup in lookups {
+        let (options, documents, destination_exists) = lookup?;
+        entries.push(PlanEntry {
+            from: format!("{}.{}", options.source_db, options.source_coll),
+            to: format!("{}.{}", options.dest_db, options.dest_coll),
+            documents,
+            destination_exists,
+            limit: options.limit,
+            filter: options.filter.clone().map(to_json),
+            match_stage: options.match_stage.clone().map(to_json),
+            projection: options.projection.clone().map(to_json),
+            write: options.write_strategy,
+            options,
+        });
+    }
+
+    Ok(CopyPlan {
+        source: mask_uri(&source.uri),
+        destination: mask_uri(&dest.uri),
+        entries,
+    })
+}
+
+fn to_json(document: Document) -> serde_json::Value {
+    Bson::Document(document).into_relaxed_extjson()
+}
@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use mongodb::bson::Document;
+use tracing::info;
+
+use mongo_copy::config::{Config, Preset};
+use mongo_copy::json::to_document;
+use mongo_copy::mongo::validate_projection;
+
+#[derive(Subcommand)]
+pub enum PresetCommand {
+    /// Save a filter and/or projection under a name, for --filter-preset
+    Add {
+        /// Name to save the preset under
+        name: String,
+
+        /// Find filter as (extended) JSON, e.g. '{"status": "active"}'
+        filter: Option<String>,
+
+        /// Projection as (extended) JSON, e.g. '{"name": 1, "email": 1}'
+        #[arg(long, value_name = "JSON")]
+        projection: Option<String>,
+
+        /// Overwrite an existing preset with the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// Delete a saved preset
+    Remove {
+        /// Name of the preset
+        name: String,
+    },
+    /// List saved presets
+    List,
+}
+
+pub fn run(command: &PresetCommand) -> Result<()> {
+    match command {
+        PresetCommand::Add {
+            name,
+            filter,
+            projection,
+            force,
+        } => add(name, filter.as_deref(), projection.as_deref(), *force),
+        PresetCommand::Remove { name } => remove(name),
+        PresetCommand::List => list(),
+    }
+}
+
+fn add(name: &str, filter: Option<&str>, projection: Option<&str>, force: bool) -> Result<()> {
+    if name.trim().is_empty() {
+        anyhow::bail!("A preset needs a name");
+    }
+    if filter.is_none() && projection.is_none() {
+        anyhow::bail!("Give a filter, a --projection, or both");
+    }
+
+    let mut config = Config::load()?;
+    if config.get_preset(name).is_some() && !force {
+        anyhow::bail!(
+            "A preset named '{}' already exists; pass --force to overwrite it",
+            name
+        );
+    }
+
+    let filter = filter
+        .map(|json| parse_value(json).context("Invalid filter"))
+        .transpose()?;
+    let projection = projection
+        .map(|json| parse_value(json).context("Invalid projection"))
+        .transpose()?;
+    let preset = Preset {
+        name: name.to_string(),
+        filter,
+        projection,
+    };
+    // Checked now so a broken preset can't fail a later copy
+    preset_documents(&preset)?;
+
+    config.add_preset(preset)?;
+    info!("Preset saved as: {}", name);
+    Ok(())
+}
+
+fn remove(name: &str) -> Result<()> {
+    let mut config = Config::load()?;
+    if !config.remove_preset(name)? {
+        anyhow::bail!("No preset named '{}'", name);
+    }
+    info!("Removed preset '{}'", name);
+    Ok(())
+}
+
+fn list() -> Result<()> {
+    let config = Config::load()?;
+    if config.presets.is_empty() {
+        println!("No saved presets");
+        return Ok(());
+    }
+    for preset in &config.presets {
+        println!("{}", preset.name);
+        if let Some(filter) = &preset.filter {
+            println!("  filter:     {}", filter);
+        }
+        if let Some(projection) = &preset.projection {
+            println!("  projection: {}", projection);
+        }
+    }
+    Ok(())
+}
+
+fn parse_value(json: &str) -> Result<serde_json::Value> {
+    let value: serde_json::Value = serde_json::from_str(json).context("Invalid JSON")?;
+    to_document(&value)?;
+    Ok(value)
+}
+
+/// The filter and projection saved in `preset`
+pub fn preset_documents(preset: &Preset) -> Result<(Option<Document>, Option<Document>)> {
+    let filter = preset
+        .filter
+        .as_ref()
+        .map(to_document)
+        .transpose()
+        .with_context(|| format!("Invalid filter in preset '{}'", preset.name))?;
+    let projection = preset
+        .projection
+        .as_ref()
+        .map(to_document)
+        .transpose()
+        .with_context(|| format!("Invalid projection in preset '{}'", preset.name))?;
+    if let Some(projection) = &projection {
+        validate_projection(projection)
+            .with_context(|| format!("Invalid projection in preset '{}'", preset.name))?;
+    }
+    Ok((filter, projection))
+}
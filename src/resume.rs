@@ -0,0 +1,116 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+use crate::uri::mask_uri;
+
+/// Collections of one database copy completed so far, saved after each one
+/// so a failed run can pick up where it stopped
+#[derive(Debug)]
+pub struct ResumeState {
+    path: PathBuf,
+    record: ResumeRecord,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct ResumeRecord {
+    /// Masked source URI
+    source: String,
+    /// Masked destination URI
+    destination: String,
+    source_db: String,
+    dest_db: String,
+    completed: Vec<String>,
+}
+
+impl ResumeState {
+    /// The saved state for copying `source_db` to `dest_db` between these
+    /// URIs, or an empty one when there is none or it was for other URIs
+    pub fn load(
+        dir: &Path,
+        source_uri: &str,
+        dest_uri: &str,
+        source_db: &str,
+        dest_db: &str,
+    ) -> Result<Self> {
+        let path = dir.join(format!("{}--{}.json", source_db, dest_db));
+        let fresh = ResumeRecord {
+            source: mask_uri(source_uri),
+            destination: mask_uri(dest_uri),
+            source_db: source_db.to_string(),
+            dest_db: dest_db.to_string(),
+            completed: Vec::new(),
+        };
+
+        let record = match fs::read_to_string(&path) {
+            Ok(content) => {
+                let saved: ResumeRecord = serde_json::from_str(&content)
+                    .with_context(|| format!("Failed to parse resume state {:?}", path))?;
+                let same_copy = saved.source == fresh.source
+                    && saved.destination == fresh.destination
+                    && saved.source_db == fresh.source_db
+                    && saved.dest_db == fresh.dest_db;
+                if same_copy {
+                    debug!(
+                        "Loaded resume state with {} completed collection(s)",
+                        saved.completed.len()
+                    );
+                    saved
+                } else {
+                    warn!(
+                        "Ignoring resume state {:?}, it is for a different source or destination",
+                        path
+                    );
+                    fresh
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => fresh,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        };
+
+        Ok(Self { path, record })
+    }
+
+    pub fn completed(&self) -> &[String] {
+        &self.record.completed
+    }
+
+    pub fn is_completed(&self, collection: &str) -> bool {
+        self.record.completed.iter().any(|c| c == collection)
+    }
+
+    /// Forget completed collections, for a run that starts over
+    pub fn reset(&mut self) -> Result<()> {
+        self.record.completed.clear();
+        self.clear()
+    }
+
+    /// Record `collection` as copied and save the state
+    pub fn mark_completed(&mut self, collection: &str) -> Result<()> {
+        if !self.is_completed(collection) {
+            self.record.completed.push(collection.to_string());
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create resume directory {:?}", dir))?;
+        }
+        let content = serde_json::to_string_pretty(&self.record)
+            .context("Failed to serialize resume state")?;
+        fs::write(&self.path, content)
+            .with_context(|| format!("Failed to write resume state {:?}", self.path))
+    }
+
+    /// Delete the saved state, once the whole database has been copied
+    pub fn clear(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => {
+                debug!("Removed resume state {:?}", self.path);
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e).with_context(|| format!("Failed to remove {:?}", self.path)),
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// JSON Schema of job files, also published for editors at
+/// `schema/job-file.schema.json`
+pub const JOB_FILE_SCHEMA: &str = include_str!("../schema/job-file.schema.json");
+
+/// One way a file breaks its schema
+#[derive(Debug)]
+pub struct Violation {
+    /// Path of the offending field, e.g. `jobs[0].limit`, empty for the
+    /// top level
+    pub field: String,
+    pub message: String,
+}
+
+impl Violation {
+    fn new(field: &str, message: String) -> Self {
+        Self {
+            field: field.to_string(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.field, self.message)
+        }
+    }
+}
+
+/// Check a parsed job file against [`JOB_FILE_SCHEMA`], returning every
+/// violation
+pub fn validate_job_file(value: &Value) -> Vec<Violation> {
+    let schema: Value =
+        serde_json::from_str(JOB_FILE_SCHEMA).expect("the bundled job file schema is valid JSON");
+    let mut violations = Vec::new();
+    check(&schema, &schema, value, "", &mut violations);
+    violations
+}
+
+/// Validate `value` against `schema`. Supports the keywords the bundled
+/// schema uses: local `$ref`, `type`, `enum`, `required`, `properties`,
+/// `additionalProperties`, `items`, `minItems`, `minLength`, `minimum` and
+/// `pattern`. Annotations such as `description` are ignored.
+fn check(root: &Value, schema: &Value, value: &Value, path: &str, out: &mut Vec<Violation>) {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+        {
+            Some(target) => check(root, target, value, path, out),
+            None => out.push(Violation::new(
+                path,
+                format!("schema reference '{}' not found", reference),
+            )),
+        }
+        return;
+    }
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.iter().any(|t| has_type(value, t)) {
+            out.push(Violation::new(
+                path,
+                format!(
+                    "expected {}, found {}",
+                    types.join(" or "),
+                    type_name(value)
+                ),
+            ));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            out.push(Violation::new(
+                path,
+                format!("{} is not one of {}", value, allowed.join(", ")),
+            ));
+        }
+    }
+
+    match value {
+        Value::Object(object) => check_object(root, schema, object, path, out),
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if (items.len() as u64) < min {
+                    out.push(Violation::new(
+                        path,
+                        format!("needs at least {} item(s)", min),
+                    ));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (idx, item) in items.iter().enumerate() {
+                    check(root, item_schema, item, &format!("{}[{}]", path, idx), out);
+                }
+            }
+        }
+        Value::String(s) => {
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if (s.chars().count() as u64) < min {
+                    out.push(Violation::new(
+                        path,
+                        format!("must be at least {} character(s)", min),
+                    ));
+                }
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                match Regex::new(pattern) {
+                    Ok(re) if re.is_match(s) => {}
+                    Ok(_) => out.push(Violation::new(
+                        path,
+                        format!("'{}' doesn't match the pattern {}", s, pattern),
+                    )),
+                    Err(e) => out.push(Violation::new(
+                        path,
+                        format!("invalid schema pattern: {}", e),
+                    )),
+                }
+            }
+        }
+        Value::Number(n) => {
+            if let (Some(min), Some(n)) =
+                (schema.get("minimum").and_then(Value::as_f64), n.as_f64())
+            {
+                if n < min {
+                    out.push(Violation::new(path, format!("must be at least {}", min)));
+                }
+            }
+        }
+        Value::Bool(_) | Value::Null => {}
+    }
+}
+
+fn check_object(
+    root: &Value,
+    schema: &Value,
+    object: &Map<String, Value>,
+    path: &str,
+    out: &mut Vec<Violation>,
+) {
+    let properties = schema.get("properties").and_then(Value::as_object);
+
+    for required in schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+    {
+        if !object.contains_key(required) {
+            out.push(Violation::new(
+                path,
+                format!("missing required field `{}`", required),
+            ));
+        }
+    }
+
+    for (key, value) in object {
+        let key_path = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        match properties.and_then(|properties| properties.get(key)) {
+            Some(property) => check(root, property, value, &key_path, out),
+            None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                let known: Vec<&str> = properties
+                    .map(|properties| properties.keys().map(String::as_str).collect())
+                    .unwrap_or_default();
+                out.push(Violation::new(
+                    &key_path,
+                    format!("unknown field (expected one of {})", known.join(", ")),
+                ));
+            }
+            None => {}
+        }
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "an object",
+        Value::Array(_) => "an array",
+        Value::String(_) => "a string",
+        Value::Number(n) if n.is_f64() => "a number",
+        Value::Number(_) => "an integer",
+        Value::Bool(_) => "a boolean",
+        Value::Null => "null",
+    }
+}
+
+/// 1-based line of `field` (a [`Violation::field`]) in a block-style YAML
+/// file. Best effort: `None` when it can't be found, e.g. in flow style or
+/// JSON.
+pub fn yaml_line(content: &str, field: &str) -> Option<usize> {
+    // (line number, indent, text without indent), skipping blanks and comments
+    let lines: Vec<(usize, usize, &str)> = content
+        .lines()
+        .enumerate()
+        .map(|(number, line)| {
+            let text = line.trim_start();
+            (number, line.len() - text.len(), text)
+        })
+        .filter(|(_, _, text)| !text.is_empty() && !text.starts_with('#'))
+        .collect();
+
+    // The current node spans the lines from `start` indented deeper than
+    // `parent`, plus the `- ` line itself when it is a sequence item
+    let mut start = 0;
+    let mut parent: Option<usize> = None;
+    let mut item_line = None;
+    let mut found = None;
+    for segment in field.split('.').filter(|s| !s.is_empty()) {
+        let (key, indexes) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+
+        let pos = (start..lines.len())
+            .take_while(|&pos| Some(pos) == item_line || parent.map_or(true, |p| lines[pos].1 > p))
+            .find(|&pos| {
+                let text = lines[pos].2;
+                let text = text.strip_prefix("- ").unwrap_or(text).trim_start();
+                let text = text.trim_start_matches('"');
+                text.strip_prefix(key)
+                    .is_some_and(|rest| rest.trim_start_matches('"').trim_start().starts_with(':'))
+            })?;
+        let (number, indent, text) = lines[pos];
+        found = Some(number);
+        start = pos + 1;
+        parent = Some(indent + text.len() - text.trim_start_matches("- ").len());
+        item_line = None;
+
+        for index in indexes
+            .split(']')
+            .filter_map(|i| i.strip_prefix('[')?.parse::<usize>().ok())
+        {
+            // Items may be indented like their key or deeper
+            let key_indent = parent.unwrap_or(0);
+            let region: Vec<usize> = (start..lines.len())
+                .take_while(|&pos| {
+                    let (_, indent, text) = lines[pos];
+                    indent > key_indent || (indent == key_indent && text.starts_with("- "))
+                })
+                .collect();
+            let dash_indent = region.first().map(|&pos| lines[pos].1)?;
+            let pos = region
+                .into_iter()
+                .filter(|&pos| lines[pos].1 == dash_indent && lines[pos].2.starts_with("- "))
+                .nth(index)?;
+            found = Some(lines[pos].0);
+            start = pos;
+            parent = Some(dash_indent);
+            item_line = Some(pos);
+        }
+    }
+    found.map(|number| number + 1)
+}
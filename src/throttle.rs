@@ -0,0 +1,54 @@
+use std::time::{Duration, Instant};
+
+/// Token bucket limiting how many documents per second a copy reads and
+/// writes, allowing bursts of up to one second's worth
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+    started: Instant,
+    acquired: u64,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: u64) -> Self {
+        let now = Instant::now();
+        Self {
+            per_second: per_second.max(1) as f64,
+            tokens: 0.0,
+            last_refill: now,
+            started: now,
+            acquired: 0,
+        }
+    }
+
+    /// Wait until a token is available, then take it
+    pub async fn acquire(&mut self) {
+        self.refill();
+        if self.tokens < 1.0 {
+            let wait = (1.0 - self.tokens) / self.per_second;
+            tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+            self.refill();
+        }
+        self.tokens -= 1.0;
+        self.acquired += 1;
+    }
+
+    /// Documents per second actually achieved since the limiter was created
+    pub fn effective_rate(&self) -> f64 {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.acquired as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.per_second);
+        self.last_refill = now;
+    }
+}
@@ -1,17 +1,167 @@
 use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode};
+use inquire::validator::Validation;
 use inquire::{Confirm, MultiSelect, Select, Text};
-use tracing::{debug, info};
+use mongodb::bson::{Bson, Document};
+use regex::Regex;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::IsTerminal;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use mongo_copy::checksum::ChecksumReport;
+use mongo_copy::config::Config;
+use mongo_copy::dump::DumpCollection;
+use mongo_copy::json::parse_document;
+use mongo_copy::keystore::KeyStore;
+use mongo_copy::mongo::{format_count, validate_projection};
+use mongo_copy::namespace::{
+    check_database_name_length, check_namespace_length, is_system_database,
+};
+use mongo_copy::uri::{normalize_uri, validate_uri};
+use mongo_copy::{
+    CollectionComparison, CopyOptions, CopyPlan, MongoConnection, StopSignal, WriteStrategy,
+    format_bytes, mask_uri,
+};
+
+/// Where a connection URI came from
+#[derive(Debug, Clone)]
+pub enum UriOrigin {
+    Argument,
+    EnvVar(String),
+    Saved(String),
+    Manual,
+    /// Not a URI at all, a `mongodump` directory
+    Dump,
+    /// An inline `uri` in a job file
+    JobFile,
+}
+
+impl fmt::Display for UriOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UriOrigin::Argument => write!(f, "command-line argument"),
+            UriOrigin::EnvVar(name) => write!(f, "env var {}", name),
+            UriOrigin::Saved(name) => write!(f, "saved URI '{}'", name),
+            UriOrigin::Manual => write!(f, "entered manually"),
+            UriOrigin::Dump => write!(f, "mongodump directory"),
+            UriOrigin::JobFile => write!(f, "job file"),
+        }
+    }
+}
+
+/// Marks collections already copied in this run in selection prompts
+const COPIED_MARK: &str = "✓ ";
+
+/// Help shown under every multi-select; → and ← are inquire's built-in
+/// select all / select none keys
+const MULTI_SELECT_HELP: &str =
+    "Use space to select, → to select all, ← to select none, type to filter, enter to confirm";
+
+/// Rows shown at once in the collection multi-select; longer lists scroll
+const COLLECTION_PAGE_SIZE: usize = 15;
+
+/// State shared by the prompts of one interactive run
+pub struct Session {
+    pub source_origin: UriOrigin,
+    pub dest_origin: UriOrigin,
+    /// Shown in every operation summary
+    pub warnings: Vec<String>,
+    /// Source namespaces (`database.collection`) copied so far
+    copied: HashSet<String>,
+    /// Raised by the stop key while a copy runs
+    pub stop: StopSignal,
+}
+
+/// Watches the terminal for the stop key while a copy runs, until dropped
+pub struct StopKey {
+    done: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Drop for StopKey {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Session {
+    pub fn new(source_origin: UriOrigin, dest_origin: UriOrigin) -> Self {
+        Self {
+            source_origin,
+            dest_origin,
+            warnings: Vec::new(),
+            copied: HashSet::new(),
+            stop: StopSignal::default(),
+        }
+    }
+
+    /// Raise `stop` when `q` then Enter is typed, until the returned guard is
+    /// dropped. Only listens when stdin is a terminal. The terminal stays in
+    /// line mode, so prompts shown afterwards are unaffected.
+    pub fn listen_for_stop(&self) -> Option<StopKey> {
+        if !std::io::stdin().is_terminal() {
+            return None;
+        }
+        self.stop.reset();
+        let stop = self.stop.clone();
+        let done = Arc::new(AtomicBool::new(false));
+        let listening = Arc::clone(&done);
+        let thread = thread::spawn(move || {
+            while !listening.load(Ordering::Relaxed) {
+                match event::poll(Duration::from_millis(200)) {
+                    Ok(true) => {
+                        if let Ok(Event::Key(key)) = event::read() {
+                            if matches!(key.code, KeyCode::Char('q' | 'Q')) && !stop.is_requested()
+                            {
+                                warn!("Stop requested, finishing the current batch...");
+                                stop.request();
+                            }
+                        }
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        debug!("Stop key listener failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+        info!("Press q then Enter to stop after the current batch");
+        Some(StopKey {
+            done,
+            thread: Some(thread),
+        })
+    }
 
-use crate::config::Config;
-use crate::keystore::KeyStore;
-use crate::mongo::MongoConnection;
+    pub fn record_copied(&mut self, database: &str, collection: &str) {
+        self.copied.insert(format!("{}.{}", database, collection));
+    }
+
+    pub fn was_copied(&self, database: &str, collection: &str) -> bool {
+        self.copied
+            .contains(&format!("{}.{}", database, collection))
+    }
+}
 
-pub fn get_mongodb_uri(env_var: &str, prompt: &str, skip_env: bool) -> Result<String> {
+pub fn get_mongodb_uri(
+    keystore: &KeyStore,
+    env_var: &str,
+    prompt: &str,
+    skip_env: bool,
+) -> Result<(String, UriOrigin)> {
     // Check environment variable first (unless skip_env is true)
     if !skip_env {
         if let Ok(uri) = std::env::var(env_var) {
             info!("Using {} from environment", env_var);
-            return Ok(uri);
+            return Ok((uri, UriOrigin::EnvVar(env_var.to_string())));
         }
     }
 
@@ -30,42 +180,89 @@ pub fn get_mongodb_uri(env_var: &str, prompt: &str, skip_env: bool) -> Result<St
         let selection = Select::new(prompt, options).prompt()?;
 
         if selection == "Enter new URI manually" {
-            prompt_and_save_uri(&config)
+            Ok((prompt_and_save_uri(&config, keystore)?, UriOrigin::Manual))
         } else if selection == "Manage saved URIs" {
-            manage_saved_uris()
+            Ok((manage_saved_uris(keystore)?, UriOrigin::Manual))
         } else {
             // Load URI from keyring
             debug!("Loading URI from keyring: {}", selection);
-            if let Some(uri) = KeyStore::get_uri(&selection)? {
-                info!("Using saved URI: {}", selection);
-                Ok(uri)
-            } else {
-                info!("URI not found in keyring, prompting for manual entry");
-                prompt_and_save_uri(&config)
+            match keystore.get_uri(&selection) {
+                Ok(Some(uri)) => {
+                    info!("Using saved URI: {}", selection);
+                    Ok((uri, UriOrigin::Saved(selection)))
+                }
+                Ok(None) => {
+                    info!("URI not found in keyring, prompting for manual entry");
+                    Ok((prompt_and_save_uri(&config, keystore)?, UriOrigin::Manual))
+                }
+                Err(e) if KeyStore::is_unavailable(&e) => {
+                    warn!(
+                        "Keyring access is unavailable, enter the URI for '{}' manually: {:#}",
+                        selection, e
+                    );
+                    Ok((prompt_uri()?, UriOrigin::Manual))
+                }
+                Err(e) => Err(e),
             }
         }
     } else {
         debug!("No saved URIs found");
-        prompt_and_save_uri(&config)
+        Ok((prompt_and_save_uri(&config, keystore)?, UriOrigin::Manual))
     }
 }
 
-fn prompt_and_save_uri(config: &Config) -> Result<String> {
+fn prompt_uri() -> Result<String> {
     let uri = Text::new("Enter MongoDB URI:")
         .with_help_message("Example: mongodb://localhost:27017")
+        .with_validator(|input: &str| match validate_uri(&normalize_uri(input)) {
+            Ok(()) => Ok(Validation::Valid),
+            Err(e) => Ok(Validation::Invalid(format!("{:#}", e).into())),
+        })
         .prompt()?;
+    Ok(normalize_uri(&uri))
+}
+
+fn prompt_and_save_uri(config: &Config, keystore: &KeyStore) -> Result<String> {
+    let uri = prompt_uri()?;
 
     let save = Confirm::new("Save this URI for future use?")
         .with_default(true)
         .prompt()?;
 
     if save {
-        let name = Text::new("Enter a name for this URI:")
-            .with_help_message("Example: production, local, staging")
+        let name = loop {
+            let name = Text::new("Enter a name for this URI:")
+                .with_help_message("Example: production, local, staging")
+                .prompt()?;
+
+            if !uri_name_exists(config, keystore, &name) {
+                break name;
+            }
+
+            let overwrite = Confirm::new(&format!(
+                "A URI named '{}' already exists, overwrite?",
+                name
+            ))
+            .with_default(false)
             .prompt()?;
 
+            if overwrite {
+                debug!("Overwriting existing URI: {}", name);
+                break name;
+            }
+        };
+
         debug!("Saving URI with name: {}", name);
-        KeyStore::store_uri(&name, &uri)?;
+        if let Err(e) = keystore.store_uri(&name, &uri) {
+            if !KeyStore::is_unavailable(&e) {
+                return Err(e);
+            }
+            warn!(
+                "Keyring access is unavailable, so the URI was not saved: {:#}",
+                e
+            );
+            return Ok(uri);
+        }
 
         let mut config = config.clone();
         config.add_uri(name.clone(), String::new())?; // Store name only in config
@@ -75,7 +272,11 @@ fn prompt_and_save_uri(config: &Config) -> Result<String> {
     Ok(uri)
 }
 
-fn manage_saved_uris() -> Result<String> {
+pub fn uri_name_exists(config: &Config, keystore: &KeyStore, name: &str) -> bool {
+    config.get_uri(name).is_some() || keystore.has_uri(name)
+}
+
+fn manage_saved_uris(keystore: &KeyStore) -> Result<String> {
     let mut config = Config::load()?;
 
     loop {
@@ -85,7 +286,7 @@ fn manage_saved_uris() -> Result<String> {
             info!("No saved URIs to manage");
             // Reload config and prompt for new URI
             let config = Config::load()?;
-            return prompt_and_save_uri(&config);
+            return prompt_and_save_uri(&config, keystore);
         }
 
         let mut options = vec!["← Back to URI selection".to_string()];
@@ -96,14 +297,14 @@ fn manage_saved_uris() -> Result<String> {
         if selection == "← Back to URI selection" {
             // Reload config and prompt for new URI
             let config = Config::load()?;
-            return prompt_and_save_uri(&config);
+            return prompt_and_save_uri(&config, keystore);
         } else if let Some(name) = selection.strip_prefix("Delete: ") {
             let confirm = Confirm::new(&format!("Delete saved URI '{}'?", name))
                 .with_default(false)
                 .prompt()?;
 
             if confirm {
-                KeyStore::delete_uri(name)?;
+                keystore.delete_uri(name)?;
                 config.remove_uri(name)?;
                 info!("Deleted saved URI: {}", name);
             }
@@ -111,31 +312,84 @@ fn manage_saved_uris() -> Result<String> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CopyMode {
     Databases,
     Collections,
+    GridFs,
 }
 
-pub fn select_copy_mode() -> Result<CopyMode> {
-    let options = vec!["Copy entire database(s)", "Copy specific collection(s)"];
-    let selection = Select::new("What would you like to copy?", options).prompt()?;
+impl CopyMode {
+    const ALL: [CopyMode; 3] = [CopyMode::Databases, CopyMode::Collections, CopyMode::GridFs];
+
+    /// Name stored in the config's remembered defaults
+    pub fn key(self) -> &'static str {
+        match self {
+            CopyMode::Databases => "databases",
+            CopyMode::Collections => "collections",
+            CopyMode::GridFs => "gridfs",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.key() == key)
+    }
+}
+
+/// Ask what to copy, starting on `default` when one was remembered
+pub fn select_copy_mode(default: Option<CopyMode>) -> Result<CopyMode> {
+    let options = vec![
+        "Copy entire database(s)",
+        "Copy specific collection(s)",
+        "Copy GridFS bucket(s)",
+    ];
+    let cursor = default
+        .and_then(|mode| CopyMode::ALL.iter().position(|m| *m == mode))
+        .unwrap_or(0);
+    let selection = Select::new("What would you like to copy?", options)
+        .with_starting_cursor(cursor)
+        .prompt()?;
 
     match selection {
         "Copy entire database(s)" => Ok(CopyMode::Databases),
         "Copy specific collection(s)" => Ok(CopyMode::Collections),
+        "Copy GridFS bucket(s)" => Ok(CopyMode::GridFs),
         _ => unreachable!(),
     }
 }
 
-pub async fn select_databases(conn: &MongoConnection) -> Result<Vec<String>> {
-    let databases = conn.list_databases().await?;
+/// Prompt for databases to copy, listing only those matching `pattern` when
+/// given. Without a terminal to prompt on, every match is selected.
+pub async fn select_databases(
+    conn: &MongoConnection,
+    pattern: Option<&Regex>,
+) -> Result<Vec<String>> {
+    let mut databases = conn.list_databases().await?;
 
     if databases.is_empty() {
         anyhow::bail!("No databases found");
     }
 
+    if let Some(pattern) = pattern {
+        let total = databases.len();
+        databases.retain(|db| pattern.is_match(db));
+        info!(
+            "Database pattern '{}' matched {} of {} database(s)",
+            pattern,
+            databases.len(),
+            total
+        );
+        if databases.is_empty() {
+            anyhow::bail!("No databases match '{}'", pattern);
+        }
+        if !std::io::stdin().is_terminal() {
+            info!("Selecting all matching databases: {}", databases.join(", "));
+            return Ok(databases);
+        }
+    }
+
     let selected = MultiSelect::new("Select database(s) to copy:", databases)
-        .with_help_message("Use space to select, enter to confirm")
+        .with_help_message(MULTI_SELECT_HELP)
         .prompt()?;
 
     Ok(selected)
@@ -153,7 +407,12 @@ pub async fn select_source_database(conn: &MongoConnection) -> Result<String> {
     Ok(selected)
 }
 
-pub async fn select_collections(conn: &MongoConnection, database: &str) -> Result<Vec<String>> {
+pub async fn select_collections(
+    conn: &MongoConnection,
+    database: &str,
+    session: &Session,
+    with_counts: bool,
+) -> Result<Vec<String>> {
     let collections = conn.list_collections(database).await?;
 
     if collections.is_empty() {
@@ -161,43 +420,127 @@ pub async fn select_collections(conn: &MongoConnection, database: &str) -> Resul
     }
 
     // Build collection names with document counts
+    let counts = if with_counts {
+        debug!("Counting documents in {} collection(s)", collections.len());
+        conn.get_display_counts(database, &collections).await
+    } else {
+        Vec::new()
+    };
     let mut collection_options = Vec::new();
-    for coll in &collections {
-        let count = conn.get_collection_count(database, coll).await.unwrap_or(0);
-        collection_options.push(format!("{} ({} documents)", coll, count));
+    for (idx, coll) in collections.iter().enumerate() {
+        let mark = if session.was_copied(database, coll) {
+            COPIED_MARK
+        } else {
+            ""
+        };
+        let option = match counts.get(idx) {
+            Some(Ok(count)) => format!("{}{} ({} documents)", mark, coll, format_count(*count)),
+            Some(Err(e)) => format!("{}{} (count unavailable: {})", mark, coll, e),
+            None => format!("{}{}", mark, coll),
+        };
+        collection_options.push(option);
     }
 
     let selected = MultiSelect::new(
         &format!("Select collection(s) from '{}' to copy:", database),
         collection_options,
     )
-    .with_help_message("Use space to select, enter to confirm")
-    .prompt()?;
-
-    // Extract original collection names from the selected options
+    .with_help_message(&format!(
+        "{}, ✓ = already copied this run",
+        MULTI_SELECT_HELP
+    ))
+    .with_page_size(COLLECTION_PAGE_SIZE)
+    .raw_prompt()?;
+
+    // Map the selected options back to collection names by position
     let selected_names: Vec<String> = selected
         .iter()
-        .map(|s| {
-            // Extract the collection name before the " (" part
-            s.split(" (").next().unwrap_or(s).to_string()
-        })
+        .map(|option| collections[option.index].clone())
         .collect();
 
     Ok(selected_names)
 }
 
-pub fn get_destination_database(source_db: &str) -> Result<String> {
-    let dest_db = Text::new("Destination database name:")
-        .with_default(source_db)
-        .with_help_message("Press enter to use the same name, or type a new name")
-        .prompt()?;
-    Ok(dest_db)
+pub async fn select_gridfs_buckets(conn: &MongoConnection, database: &str) -> Result<Vec<String>> {
+    let buckets = conn.list_gridfs_buckets(database).await?;
+
+    if buckets.is_empty() {
+        anyhow::bail!("No GridFS buckets found in database '{}'", database);
+    }
+
+    let selected = MultiSelect::new(
+        &format!("Select GridFS bucket(s) from '{}' to copy:", database),
+        buckets,
+    )
+    .with_help_message(MULTI_SELECT_HELP)
+    .prompt()?;
+
+    Ok(selected)
 }
 
-pub fn get_destination_collection(source_coll: &str) -> Result<String> {
+pub fn select_dump_collections(dumps: &[DumpCollection]) -> Result<Vec<DumpCollection>> {
+    let options: Vec<String> = dumps
+        .iter()
+        .map(|d| format!("{}.{} ({})", d.database, d.collection, format_bytes(d.size)))
+        .collect();
+
+    let selected = MultiSelect::new("Select collection(s) to restore from the dump:", options)
+        .with_help_message(MULTI_SELECT_HELP)
+        .raw_prompt()?;
+
+    Ok(selected.iter().map(|o| dumps[o.index].clone()).collect())
+}
+
+pub fn get_destination_database(default: &str, allow_system: bool) -> Result<String> {
+    loop {
+        let dest_db = Text::new("Destination database name:")
+            .with_default(default)
+            .with_help_message("Press enter to accept the suggested name, or type a new name")
+            .with_validator(move |input: &str| {
+                if is_system_database(input) && !allow_system {
+                    Ok(Validation::Invalid(
+                        format!(
+                            "'{}' is a reserved system database; pass --allow-system-dest to copy into it",
+                            input
+                        )
+                        .into(),
+                    ))
+                } else if let Err(e) = check_database_name_length(input) {
+                    Ok(Validation::Invalid(e.to_string().into()))
+                } else {
+                    Ok(Validation::Valid)
+                }
+            })
+            .prompt()?;
+
+        if !is_system_database(&dest_db) || confirm_system_destination(&dest_db)? {
+            return Ok(dest_db);
+        }
+    }
+}
+
+/// Ask before writing into `admin`, `local` or `config`
+pub fn confirm_system_destination(database: &str) -> Result<bool> {
+    let confirmed = Confirm::new(&format!(
+        "'{}' is a reserved system database. Copy into it anyway?",
+        database
+    ))
+    .with_default(false)
+    .prompt()?;
+    Ok(confirmed)
+}
+
+pub fn get_destination_collection(database: &str, default: &str) -> Result<String> {
+    let database = database.to_string();
     let dest_coll = Text::new("Destination collection name:")
-        .with_default(source_coll)
+        .with_default(default)
         .with_help_message("Press enter to use the same name, or type a new name")
+        .with_validator(move |input: &str| {
+            Ok(match check_namespace_length(&database, input) {
+                Ok(()) => Validation::Valid,
+                Err(e) => Validation::Invalid(e.to_string().into()),
+            })
+        })
         .prompt()?;
     Ok(dest_coll)
 }
@@ -207,12 +550,16 @@ pub async fn get_copy_limit(
     database: &str,
     collection: &str,
 ) -> Result<Option<u64>> {
-    let count = conn.get_collection_count(database, collection).await?;
-
-    println!(
-        "Collection '{}' has approximately {} documents",
-        collection, count
-    );
+    match conn.get_display_count(database, collection).await? {
+        Some(count) => println!(
+            "Collection '{}' has approximately {} documents",
+            collection, count
+        ),
+        None => println!(
+            "Collection '{}' has an unknown number of documents (stats unavailable)",
+            collection
+        ),
+    }
 
     let copy_all = Confirm::new("Copy all documents?")
         .with_default(true)
@@ -233,29 +580,316 @@ pub async fn get_copy_limit(
     }
 }
 
-pub fn confirm_operation(source_uri: &str, dest_uri: &str, operation: &str) -> Result<bool> {
+/// Ask before connecting to a remote host without TLS
+pub fn confirm_plaintext_connection(label: &str, uri: &str) -> Result<bool> {
+    let confirmed = Confirm::new(&format!(
+        "The {} connection to {} does not use TLS. Continue anyway?",
+        label,
+        mask_uri(uri)
+    ))
+    .with_default(false)
+    .prompt()?;
+    Ok(confirmed)
+}
+
+/// Ask for an optional find filter, checked as extended JSON while typing
+pub fn get_copy_filter() -> Result<Option<Document>> {
+    prompt_optional_document(
+        "Filter (optional):",
+        "Extended JSON, e.g. {\"status\": \"active\"}; leave empty to copy every document",
+        |_| Ok(()),
+    )
+}
+
+/// Ask for an optional projection, rejecting mixed inclusion and exclusion
+pub fn get_copy_projection() -> Result<Option<Document>> {
+    prompt_optional_document(
+        "Projection (optional):",
+        "Extended JSON, e.g. {\"name\": 1, \"email\": 1}; leave empty to copy whole documents",
+        validate_projection,
+    )
+}
+
+/// Prompt for a JSON document, refusing to submit until the input is empty
+/// or parses and passes `check`
+fn prompt_optional_document(
+    message: &str,
+    help: &str,
+    check: fn(&Document) -> Result<()>,
+) -> Result<Option<Document>> {
+    let input = Text::new(message)
+        .with_help_message(help)
+        .with_validator(move |input: &str| {
+            if input.trim().is_empty() {
+                return Ok(Validation::Valid);
+            }
+            match parse_document(input).and_then(|doc| check(&doc)) {
+                Ok(()) => Ok(Validation::Valid),
+                Err(e) => Ok(Validation::Invalid(format!("{:#}", e).into())),
+            }
+        })
+        .prompt()?;
+
+    if input.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(parse_document(&input)?))
+}
+
+pub fn copy_something_else() -> Result<bool> {
+    let again = Confirm::new("Copy something else?")
+        .with_default(false)
+        .prompt()?;
+    Ok(again)
+}
+
+/// Answer to [`confirm_or_edit_operation`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    Proceed,
+    Abort,
+    /// Re-prompt the destination and limit, then ask again
+    Edit,
+}
+
+impl fmt::Display for Confirmation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Confirmation::Proceed => write!(f, "Yes"),
+            Confirmation::Abort => write!(f, "No"),
+            Confirmation::Edit => write!(f, "Edit"),
+        }
+    }
+}
+
+pub fn confirm_operation(
+    session: &Session,
+    source_uri: &str,
+    dest_uri: &str,
+    operation: &str,
+) -> Result<bool> {
+    print_operation_summary(session, source_uri, dest_uri, operation);
+
+    let confirmed = Confirm::new("Proceed with this operation?")
+        .with_default(false)
+        .prompt()?;
+
+    Ok(confirmed)
+}
+
+/// Like [`confirm_operation`], but also offers going back to fix the
+/// destination instead of skipping the operation
+pub fn confirm_or_edit_operation(
+    session: &Session,
+    source_uri: &str,
+    dest_uri: &str,
+    operation: &str,
+) -> Result<Confirmation> {
+    print_operation_summary(session, source_uri, dest_uri, operation);
+
+    let choice = Select::new(
+        "Proceed with this operation?",
+        vec![
+            Confirmation::Proceed,
+            Confirmation::Abort,
+            Confirmation::Edit,
+        ],
+    )
+    .with_starting_cursor(1)
+    .with_help_message("Edit re-asks for the destination and limit")
+    .prompt()?;
+
+    Ok(choice)
+}
+
+fn print_operation_summary(session: &Session, source_uri: &str, dest_uri: &str, operation: &str) {
     println!("\n{}", "=".repeat(80));
     println!("OPERATION SUMMARY");
     println!("{}", "=".repeat(80));
-    println!("Source:      {}", mask_uri(source_uri));
-    println!("Destination: {}", mask_uri(dest_uri));
+    println!(
+        "Source:      {} (from {})",
+        mask_uri(source_uri),
+        session.source_origin
+    );
+    println!(
+        "Destination: {} (from {})",
+        mask_uri(dest_uri),
+        session.dest_origin
+    );
     println!("Operation:   {}", operation);
+    for warning in &session.warnings {
+        println!("Warning:     {}", warning);
+    }
     println!("{}", "=".repeat(80));
+}
 
-    let confirmed = Confirm::new("Proceed with this operation?")
-        .with_default(false)
-        .prompt()?;
+/// Table of every namespace in `plan`, with any per-entry options underneath
+pub fn print_plan(plan: &CopyPlan) {
+    let width = plan
+        .entries
+        .iter()
+        .map(|entry| entry.from.chars().count().max(entry.to.chars().count()))
+        .max()
+        .unwrap_or(0)
+        .max("Destination".len());
 
-    Ok(confirmed)
+    println!("\n{}", "=".repeat(80));
+    println!("COPY PLAN");
+    println!("{}", "=".repeat(80));
+    println!("Source:      {}", plan.source);
+    println!("Destination: {}", plan.destination);
+    println!(
+        "\n  {:<width$} {:<width$} {:>16}  Existing",
+        "Source", "Destination", "Documents (est.)"
+    );
+    for entry in &plan.entries {
+        println!(
+            "  {:<width$} {:<width$} {:>16}  {}",
+            entry.from,
+            entry.to,
+            format_count(entry.documents),
+            if entry.destination_exists {
+                "yes"
+            } else {
+                "no"
+            }
+        );
+        if let Some(limit) = entry.limit {
+            println!("      limit:      {}", limit);
+        }
+        if let Some(filter) = &entry.filter {
+            println!("      filter:     {}", filter);
+        }
+        if let Some(stage) = &entry.match_stage {
+            println!("      $match:     {}", stage);
+        }
+        if let Some(projection) = &entry.projection {
+            println!("      projection: {}", projection);
+        }
+        if entry.write != WriteStrategy::Insert {
+            println!("      write:      {:?}", entry.write);
+        }
+    }
+    println!(
+        "\n  {} collection(s), ~{} documents",
+        plan.entries.len(),
+        plan.total_documents()
+    );
+    println!("{}", "=".repeat(80));
 }
 
-fn mask_uri(uri: &str) -> String {
-    if let Some(at_pos) = uri.find('@') {
-        if let Some(protocol_end) = uri.find("://") {
-            let protocol = &uri[..protocol_end + 3];
-            let after_at = &uri[at_pos..];
-            return format!("{}***{}", protocol, after_at);
+/// Table of a database's collections and estimated document counts, shown
+/// before confirming a whole-database copy
+pub fn print_collection_preview(database: &str, counts: &[(String, Result<Option<u64>>)]) {
+    let width = counts
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Collection".len());
+
+    println!("\nCollections in '{}':", database);
+    println!("  {:<width$} {:>16}", "Collection", "Documents (est.)");
+    for (name, count) in counts {
+        match count {
+            Ok(count) => println!("  {:<width$} {:>16}", name, format_count(*count)),
+            Err(e) => println!("  {:<width$} {:>16}  {:#}", name, "unavailable", e),
         }
     }
-    uri.to_string()
+    let total: u64 = counts
+        .iter()
+        .filter_map(|(_, count)| count.as_ref().ok().copied().flatten())
+        .sum();
+    let unknown = if counts
+        .iter()
+        .any(|(_, count)| !matches!(count, Ok(Some(_))))
+    {
+        " (+ unknown)"
+    } else {
+        ""
+    };
+    println!("  {:<width$} {:>16}{}", "Total", total, unknown);
+}
+
+/// Longest preview shown for one sample document, in characters
+const MAX_SAMPLE_CHARS: usize = 2000;
+
+/// Pretty-print sample source documents as relaxed extended JSON, cutting
+/// very large ones short
+pub fn print_sample_documents(namespace: &str, documents: &[Document]) {
+    if documents.is_empty() {
+        println!("\nNo documents in '{}' match", namespace);
+        return;
+    }
+    println!("\nSample documents from '{}':", namespace);
+    for (idx, document) in documents.iter().enumerate() {
+        let json = Bson::Document(document.clone()).into_relaxed_extjson();
+        let text = serde_json::to_string_pretty(&json).unwrap_or_else(|_| document.to_string());
+        let total = text.chars().count();
+        println!("--- {} of {} ---", idx + 1, documents.len());
+        if total > MAX_SAMPLE_CHARS {
+            let shown: String = text.chars().take(MAX_SAMPLE_CHARS).collect();
+            println!(
+                "{}\n... ({} more characters)",
+                shown,
+                total - MAX_SAMPLE_CHARS
+            );
+        } else {
+            println!("{}", text);
+        }
+    }
+}
+
+pub fn print_checksum_report(options: &CopyOptions, report: &ChecksumReport) {
+    let ids = |ids: &[Bson]| {
+        ids.iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    println!(
+        "\nChecksums: '{}.{}' -> '{}.{}'",
+        options.source_db, options.source_coll, options.dest_db, options.dest_coll
+    );
+    println!("  {:<14} {:>15}", "Checked", report.checked);
+    println!("  {:<14} {:>15}", "Mismatched", report.mismatched);
+    if !report.mismatched_ids.is_empty() {
+        println!("    _id: {}", ids(&report.mismatched_ids));
+    }
+    println!("  {:<14} {:>15}", "Missing", report.missing);
+    if !report.missing_ids.is_empty() {
+        println!("    _id: {}", ids(&report.missing_ids));
+    }
+}
+
+pub fn print_comparison(options: &CopyOptions, comparison: &CollectionComparison) {
+    let mark = |matches: bool| if matches { "" } else { "  <-- MISMATCH" };
+
+    println!(
+        "\nComparison: '{}.{}' -> '{}.{}'",
+        options.source_db, options.source_coll, options.dest_db, options.dest_coll
+    );
+    println!("  {:<14} {:>15} {:>15}", "", "Source", "Destination");
+    println!(
+        "  {:<14} {:>15} {:>15}{}",
+        "Documents",
+        comparison.source.documents,
+        comparison.dest.documents,
+        mark(comparison.documents_match())
+    );
+    println!(
+        "  {:<14} {:>15} {:>15}{}",
+        "Indexes",
+        comparison.source.indexes,
+        comparison.dest.indexes,
+        mark(comparison.indexes_match())
+    );
+    println!(
+        "  {:<14} {:>15.1} {:>15.1}{}",
+        "Avg doc size",
+        comparison.source.avg_document_size,
+        comparison.dest.avg_document_size,
+        mark(comparison.avg_document_size_matches())
+    );
 }
@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use mongodb::options::{ConnectionString, HostInfo, ServerAddress, Tls};
+use std::net::IpAddr;
+
+/// Hide the credentials portion of a MongoDB URI for display. Only the
+/// authority (up to the first `/` or `?`) is searched for `@`, so seed lists
+/// like `mongodb://user:pw@h1,h2,h3/` mask cleanly and an `@` in the path or
+/// options of a credential-less URI is left alone.
+pub fn mask_uri(uri: &str) -> String {
+    let Some(protocol_end) = uri.find("://") else {
+        return uri.to_string();
+    };
+    let rest = &uri[protocol_end + 3..];
+    let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+    match rest[..authority_end].rfind('@') {
+        Some(at_pos) => format!("{}***{}", &uri[..protocol_end + 3], &rest[at_pos..]),
+        None => uri.to_string(),
+    }
+}
+
+/// Clean up a pasted URI: trim whitespace and strip surrounding quotes
+pub fn normalize_uri(input: &str) -> String {
+    let trimmed = input.trim();
+    let unquoted = ['"', '\'']
+        .iter()
+        .find_map(|&q| {
+            trimmed
+                .strip_prefix(q)
+                .and_then(|rest| rest.strip_suffix(q))
+        })
+        .unwrap_or(trimmed);
+    unquoted.trim().to_string()
+}
+
+/// Check that a URI parses, without resolving DNS or connecting
+pub fn validate_uri(uri: &str) -> Result<()> {
+    ConnectionString::parse(uri).context("Invalid MongoDB URI")?;
+    Ok(())
+}
+
+/// Whether `uri` would send credentials and data unencrypted to a host other
+/// than this machine. SRV URIs enable TLS unless it is explicitly turned off.
+pub fn is_plaintext_remote(uri: &str) -> Result<bool> {
+    let conn = ConnectionString::parse(uri).context("Invalid MongoDB URI")?;
+
+    let tls_enabled = match &conn.tls {
+        Some(Tls::Enabled(_)) => true,
+        Some(Tls::Disabled) => false,
+        None => matches!(conn.host_info, HostInfo::DnsRecord(_)),
+    };
+    if tls_enabled {
+        return Ok(false);
+    }
+
+    let remote = match &conn.host_info {
+        HostInfo::HostIdentifiers(hosts) => hosts.iter().any(|host| match host {
+            ServerAddress::Tcp { host, .. } => !is_loopback_host(host),
+            _ => false,
+        }),
+        _ => true,
+    };
+    Ok(remote)
+}
+
+fn is_loopback_host(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_credentials_of_multi_host_uris() {
+        assert_eq!(
+            mask_uri("mongodb://u:p@h1,h2,h3/db"),
+            "mongodb://***@h1,h2,h3/db"
+        );
+        assert_eq!(
+            mask_uri("mongodb://u:p@h1:27017,h2:27018/db?replicaSet=rs0"),
+            "mongodb://***@h1:27017,h2:27018/db?replicaSet=rs0"
+        );
+        assert_eq!(
+            mask_uri("mongodb+srv://u:p@host/"),
+            "mongodb+srv://***@host/"
+        );
+    }
+
+    #[test]
+    fn leaves_uris_without_credentials_alone() {
+        assert_eq!(mask_uri("mongodb://h1,h2/"), "mongodb://h1,h2/");
+        assert_eq!(mask_uri("mongodb://h1,h2"), "mongodb://h1,h2");
+        assert_eq!(mask_uri("not a uri"), "not a uri");
+    }
+
+    #[test]
+    fn ignores_at_signs_in_query_options() {
+        assert_eq!(
+            mask_uri("mongodb://h1,h2/?appName=ops@example"),
+            "mongodb://h1,h2/?appName=ops@example"
+        );
+        assert_eq!(
+            mask_uri("mongodb://h1,h2?appName=ops@example"),
+            "mongodb://h1,h2?appName=ops@example"
+        );
+        assert_eq!(
+            mask_uri("mongodb://u:p@h1,h2/db?appName=ops@example"),
+            "mongodb://***@h1,h2/db?appName=ops@example"
+        );
+    }
+
+    #[test]
+    fn validates_multi_host_and_srv_uris() {
+        assert!(validate_uri("mongodb://u:p@h1,h2,h3/db").is_ok());
+        assert!(validate_uri("mongodb://h1,h2/").is_ok());
+        assert!(validate_uri("mongodb+srv://u:p@host.example.com/").is_ok());
+        assert!(validate_uri("mongodb://h1,h2/?appName=ops@example").is_ok());
+        assert!(validate_uri("mongodb://u:p@h1,h2/db?appName=ops@example").is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed_uris() {
+        assert!(validate_uri("http://h1,h2/").is_err());
+        assert!(validate_uri("mongodb://").is_err());
+        // SRV URIs name exactly one host
+        assert!(validate_uri("mongodb+srv://u:p@h1,h2/").is_err());
+    }
+}
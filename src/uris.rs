@@ -0,0 +1,143 @@
+use anyhow::Result;
+use clap::Subcommand;
+use std::time::Instant;
+use tracing::info;
+
+use crate::Cli;
+use crate::exit::ConnectionFailed;
+use crate::ui::uri_name_exists;
+use mongo_copy::config::Config;
+use mongo_copy::keystore::KeyStore;
+use mongo_copy::uri::{normalize_uri, validate_uri};
+use mongo_copy::{MongoConnection, mask_uri};
+
+#[derive(Subcommand)]
+pub enum UrisCommand {
+    /// Save a URI under a name
+    Add {
+        /// Name to save the URI under
+        name: String,
+
+        /// MongoDB connection string
+        uri: String,
+
+        /// Overwrite an existing URI with the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// Compare saved names in the config against the keyring and fix drift
+    Reconcile {
+        /// Add keyring entries missing from the config back into it
+        #[arg(long)]
+        restore: bool,
+
+        /// Remove config names with no stored URI, and keyring entries not in the config
+        #[arg(long)]
+        clean: bool,
+    },
+    /// Connect to a saved URI and ping it, without copying anything
+    Test {
+        /// Name of the saved URI
+        name: String,
+    },
+}
+
+pub async fn run(command: &UrisCommand, cli: &Cli) -> Result<()> {
+    match command {
+        UrisCommand::Add { name, uri, force } => add(&cli.keystore(), name, uri, *force),
+        UrisCommand::Reconcile { restore, clean } => reconcile(&cli.keystore(), *restore, *clean),
+        UrisCommand::Test { name } => test(name, cli).await,
+    }
+}
+
+fn add(keystore: &KeyStore, name: &str, uri: &str, force: bool) -> Result<()> {
+    let mut config = Config::load()?;
+
+    if uri_name_exists(&config, keystore, name) && !force {
+        anyhow::bail!(
+            "A URI named '{}' already exists; pass --force to overwrite it",
+            name
+        );
+    }
+
+    let uri = normalize_uri(uri);
+    validate_uri(&uri)?;
+
+    keystore.store_uri(name, &uri)?;
+    config.add_uri(name.to_string(), String::new())?; // Store name only in config
+    info!("URI saved as: {}", name);
+    Ok(())
+}
+
+async fn test(name: &str, cli: &Cli) -> Result<()> {
+    let uri = crate::saved_uri(&cli.keystore(), name)?;
+    crate::check_plaintext(cli, name, &uri, false)?;
+
+    println!("Testing '{}' ({})", name, mask_uri(&uri));
+    let started = Instant::now();
+    // Connecting already pings, unless --skip-connection-test
+    let conn = MongoConnection::connect_with_retries(
+        &uri,
+        cli.connect_retries,
+        crate::connect_options(cli, false),
+    )
+    .await
+    .map_err(|e| e.into_inner().context(ConnectionFailed("saved")))?;
+    let connect_time = started.elapsed();
+    let version = conn
+        .server_version()
+        .await
+        .map_err(|e| e.context(ConnectionFailed("saved")))?;
+
+    println!(
+        "OK: connected in {} ms, server version {}",
+        connect_time.as_millis(),
+        version
+    );
+    Ok(())
+}
+
+fn reconcile(keystore: &KeyStore, restore: bool, clean: bool) -> Result<()> {
+    let mut config = Config::load()?;
+    let drift = keystore.reconcile(&config)?;
+
+    if drift.is_empty() {
+        info!("Config and keyring are in sync");
+        return Ok(());
+    }
+
+    for name in &drift.missing_from_keyring {
+        println!("Config only:  {} (no URI stored in keyring)", name);
+    }
+    for name in &drift.missing_from_config {
+        println!("Keyring only: {} (not listed in config)", name);
+    }
+
+    if restore {
+        for name in &drift.missing_from_config {
+            config.add_uri(name.clone(), String::new())?;
+            info!("Restored '{}' to config", name);
+        }
+    }
+
+    if clean {
+        for name in &drift.missing_from_keyring {
+            config.remove_uri(name)?;
+            info!("Removed '{}' from config", name);
+        }
+        if !restore {
+            for name in &drift.missing_from_config {
+                keystore.delete_uri(name)?;
+                info!("Removed '{}' from keyring", name);
+            }
+        }
+    }
+
+    if !restore && !clean {
+        info!(
+            "Run with --restore to re-add keyring entries to the config, or --clean to remove orphans"
+        );
+    }
+
+    Ok(())
+}